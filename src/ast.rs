@@ -1,23 +1,197 @@
-#[derive(Debug, Clone)]
-
-pub enum AstNode {
-    Function { name: String, body: Vec<AstNode> },
-    Unsafe { body: Vec<AstNode> },
-
-    Region { kind: String, name: String },
-    Lifetime { name: String, scope: String },
-    Let { name: String, region: String },
-
-    Capability {
-        kind: String,
-        value: String,
-        lifetime: String,
-    },
-
-    Drop { value: String },
-    Assume { text: String },
-
-    PhaseDecl {
-        name: String,
-    },
-}
+use std::fmt;
+
+/// A position in the source file (1-based line and column).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, serde::Serialize)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct AstNode {
+    pub kind: NodeKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+
+pub enum NodeKind {
+    /// `params` is empty for a function declared without a parameter list.
+    Function { name: String, params: Vec<Param>, body: Vec<AstNode> },
+    /// A plain nested scope; `name` lets lifetimes and moves target it.
+    Block { name: Option<String>, body: Vec<AstNode> },
+    Unsafe { body: Vec<AstNode> },
+    Declassify { body: Vec<AstNode> },
+    Spawn { body: Vec<AstNode> },
+    /// `condition` only names the branch; both paths are always verified.
+    /// `else_body` is empty when there is no `else`.
+    If { condition: String, then_body: Vec<AstNode>, else_body: Vec<AstNode> },
+    /// `count` is `None` for an unbounded `loop` and `Some(n)` for `repeat n`.
+    Loop { count: Option<usize>, body: Vec<AstNode> },
+
+    /// `label` comes from a trailing `label L`. `assumption` is the text of
+    /// a trailing `assume "..."`, which lets an external region be declared
+    /// outside unsafe code.
+    Region {
+        kind: String,
+        name: String,
+        label: Option<String>,
+        assumption: Option<String>,
+    },
+    Lifetime { name: String, scope: String },
+    /// `sources` lists the values named in `let b from a in r`; it is
+    /// empty for a plain allocation.
+    Let {
+        name: String,
+        region: String,
+        sources: Vec<String>,
+        secret: bool,
+    },
+
+    /// `name` is set when the grant is declared with `as NAME`; `parent`
+    /// when it is reborrowed from another capability with `from`.
+    Capability {
+        kind: String,
+        value: String,
+        lifetime: String,
+        name: Option<String>,
+        parent: Option<CapabilityRef>,
+    },
+    Release { target: CapabilityRef },
+    Downgrade { target: CapabilityRef, kind: String },
+
+    Copy { from: String, into: String },
+    Drop { value: String },
+    Zeroize { value: String },
+    Return { value: String, region: String },
+    Move { value: String, target: String },
+
+    Read { value: String },
+    Write { value: String },
+    Call { function: String, values: Vec<String> },
+    /// `call f(a, b)` of a function declared in the program, as opposed to
+    /// the opaque `call f with a, b`.
+    CallFunction { function: String, args: Vec<String> },
+
+    /// `affects` is `None` when omitted; the affected values are then
+    /// inferred from the enclosing unsafe block.
+    Assume { text: String, affects: Option<Vec<String>> },
+
+    PhaseDecl {
+        name: String,
+    },
+    Advance {
+        phase: String,
+    },
+}
+
+/// A function parameter and the capability kind a caller must hold on the
+/// argument for the duration of the call.
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub name: String,
+    pub capability: String,
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.capability)
+    }
+}
+
+/// A reference to a live capability: by the name given with `as NAME`, or
+/// as the most recently granted capability of a kind on a value.
+#[derive(Debug, Clone)]
+pub enum CapabilityRef {
+    Named(String),
+    Kind { kind: String, value: String },
+}
+
+impl fmt::Display for CapabilityRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CapabilityRef::Named(name) => write!(f, "{}", name),
+            CapabilityRef::Kind { kind, value } => write!(f, "{} {}", kind, value),
+        }
+    }
+}
+
+/// Renders the statement header as written, without block bodies.
+impl fmt::Display for NodeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeKind::Function { name, params, .. } => {
+                write!(f, "function {}", name)?;
+                if !params.is_empty() {
+                    let params: Vec<String> = params.iter().map(|p| p.to_string()).collect();
+                    write!(f, "({})", params.join(", "))?;
+                }
+                Ok(())
+            }
+            NodeKind::Block { name: None, .. } => write!(f, "block"),
+            NodeKind::Block { name: Some(name), .. } => write!(f, "block {}", name),
+            NodeKind::Unsafe { .. } => write!(f, "unsafe"),
+            NodeKind::Declassify { .. } => write!(f, "declassify"),
+            NodeKind::Spawn { .. } => write!(f, "spawn"),
+            NodeKind::If { condition, .. } => write!(f, "if {}", condition),
+            NodeKind::Loop { count: None, .. } => write!(f, "loop"),
+            NodeKind::Loop { count: Some(n), .. } => write!(f, "repeat {}", n),
+            NodeKind::Region { kind, name, label, assumption } => {
+                write!(f, "region {} {}", kind, name)?;
+                if let Some(label) = label {
+                    write!(f, " label {}", label)?;
+                }
+                if let Some(text) = assumption {
+                    write!(f, " assume \"{}\"", text)?;
+                }
+                Ok(())
+            }
+            NodeKind::Lifetime { name, scope } => {
+                write!(f, "lifetime {} bound to {}", name, scope)
+            }
+            NodeKind::Let { name, region, sources, secret } => {
+                write!(f, "let {}{}", if *secret { "secret " } else { "" }, name)?;
+                if !sources.is_empty() {
+                    write!(f, " from {}", sources.join(", "))?;
+                }
+                write!(f, " in {}", region)
+            }
+            NodeKind::Capability { kind, value, lifetime, name, parent } => {
+                write!(f, "capability {} {} during {}", kind, value, lifetime)?;
+                if let Some(parent) = parent {
+                    write!(f, " from {}", parent)?;
+                }
+                if let Some(name) = name {
+                    write!(f, " as {}", name)?;
+                }
+                Ok(())
+            }
+            NodeKind::Release { target } => write!(f, "release {}", target),
+            NodeKind::Downgrade { target, kind } => {
+                write!(f, "downgrade {} to {}", target, kind)
+            }
+            NodeKind::Copy { from, into } => write!(f, "copy {} into {}", from, into),
+            NodeKind::Drop { value } => write!(f, "drop {}", value),
+            NodeKind::Zeroize { value } => write!(f, "zeroize {}", value),
+            NodeKind::Return { value, region } => write!(f, "return {} to {}", value, region),
+            NodeKind::Move { value, target } => write!(f, "move {} to {}", value, target),
+            NodeKind::Read { value } => write!(f, "read {}", value),
+            NodeKind::Write { value } => write!(f, "write {}", value),
+            NodeKind::Call { function, values } => {
+                write!(f, "call {} with {}", function, values.join(", "))
+            }
+            NodeKind::CallFunction { function, args } => {
+                write!(f, "call {}({})", function, args.join(", "))
+            }
+            NodeKind::Assume { text, affects } => {
+                write!(f, "assume \"{}\"", text)?;
+                if let Some(values) = affects {
+                    write!(f, " affects {}", values.join(", "))?;
+                }
+                Ok(())
+            }
+            NodeKind::PhaseDecl { name } => write!(f, "phase {}", name),
+            NodeKind::Advance { phase } => write!(f, "advance to {}", phase),
+        }
+    }
+}
@@ -0,0 +1,575 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::violation::ViolationKind;
+
+/// Every semantic error the verifier can raise.
+///
+/// Each variant has a stable code (see `code`). Codes are never reused or
+/// renumbered; `chiru explain <code>` prints the long-form explanation.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum ChiruError {
+    UnknownValue { value: String },
+    UnknownRegion { region: String },
+    UnknownLifetime { lifetime: String },
+    UnknownRegionKind { kind: String },
+    UnknownCapabilityKind { kind: String },
+    UnknownPhase { phase: String },
+    DuplicatePhase { phase: String },
+    PhaseNotAtModuleLevel { phase: String },
+    PhaseDeclaredLate { phase: String },
+    PhaseRegression { from: String, to: String },
+    PhaseSkipped { from: String, to: String, skipped: Vec<String> },
+    CapabilityConflict { value: String, held: String, requested: String },
+    CapabilityOnDestroyedValue { value: String },
+    InactiveLifetime { lifetime: String },
+    LifetimePhaseMismatch { lifetime: String, created_in: String, used_in: String },
+    AssumptionOutsideUnsafe,
+    DoubleDrop { value: String },
+    DropNotOwned { value: String, scope: String },
+    DropWithActiveCapabilities { value: String },
+    NoActiveScope,
+    UnknownScope { lifetime: String, scope: String },
+    ScopeNotEnclosing { lifetime: String, scope: String },
+    DuplicateDeclaration { kind: String, name: String },
+    OutOfScope { kind: String, name: String },
+    MissingThreadCapability { value: String },
+    DataRace { value: String },
+    UseAfterFree { value: String },
+    MissingCapability { value: String, access: String },
+    ExpiredLifetime { value: String, lifetime: String },
+    WriteWithoutUniqueMut { value: String, held: Vec<String> },
+    MoveTargetNotEnclosing { value: String, scope: String },
+    UseAfterMove { value: String },
+    MoveNotOwned { value: String },
+    MoveWithActiveCapabilities { value: String },
+    UnknownCapability { capability: String },
+    CapabilityNotHeld { value: String, kind: String },
+    CapabilityNotLive { capability: String },
+    InvalidDowngrade { value: String, from: String, to: String },
+    ReborrowExceedsParent { value: String, parent: String, requested: String },
+    ReborrowOutlivesParent { value: String, lifetime: String, parent_lifetime: String },
+    CapabilitySuspended { value: String, kind: String },
+    ReborrowOfOtherValue { value: String, parent_value: String },
+    Leak { value: String, scope: String },
+    ValueOutlivesRegion { value: String, region: String },
+    CapabilityOutlivesRegion { value: String, lifetime: String, region: String },
+    InactiveRegion { region: String },
+    ExternalDrop { value: String, region: String },
+    ExternalNotReturned { value: String, region: String },
+    ReturnToWrongRegion { value: String, region: String, allocated_in: String },
+    ReturnNotExternal { value: String, region: String },
+    StaticDrop { value: String },
+    ExternalRegionOutsideUnsafe { region: String },
+    SecretNotZeroized { value: String },
+    InformationFlow { from: String, to: String, from_label: String, to_label: String },
+    DeclassifyOutsideUnsafe,
+    DeclassifyWithoutAssumption { from: String, to: String },
+    UnknownLabel { label: String },
+    UnknownFunction { function: String },
+    ArityMismatch { function: String, expected: usize, found: usize },
+    MissingCallCapability { function: String, value: String, required: String },
+    AliasedArgument { function: String, value: String },
+    Recursion { cycle: Vec<String> },
+    BranchInconsistency { value: String, action: String, path: String },
+    BranchPhaseMismatch { then_phase: String, else_phase: String },
+    LoopDivergence { iteration: usize, values: Vec<String> },
+    CapturedName { kind: String, name: String, function: String },
+    BranchOwnerMismatch { value: String, owner: String, path: String },
+    BranchReleaseMismatch { kind: String, value: String, path: String },
+}
+
+impl ChiruError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ChiruError::UnknownValue { .. } => "CH0001",
+            ChiruError::UnknownRegion { .. } => "CH0002",
+            ChiruError::UnknownLifetime { .. } => "CH0003",
+            ChiruError::UnknownRegionKind { .. } => "CH0004",
+            ChiruError::UnknownCapabilityKind { .. } => "CH0005",
+            ChiruError::UnknownPhase { .. } => "CH0006",
+            ChiruError::DuplicatePhase { .. } => "CH0007",
+            ChiruError::PhaseNotAtModuleLevel { .. } => "CH0008",
+            ChiruError::PhaseDeclaredLate { .. } => "CH0009",
+            ChiruError::PhaseRegression { .. } => "CH0010",
+            ChiruError::PhaseSkipped { .. } => "CH0011",
+            ChiruError::CapabilityConflict { .. } => "CH0012",
+            ChiruError::CapabilityOnDestroyedValue { .. } => "CH0013",
+            ChiruError::InactiveLifetime { .. } => "CH0014",
+            ChiruError::LifetimePhaseMismatch { .. } => "CH0015",
+            ChiruError::AssumptionOutsideUnsafe => "CH0016",
+            ChiruError::DoubleDrop { .. } => "CH0017",
+            ChiruError::DropNotOwned { .. } => "CH0018",
+            ChiruError::DropWithActiveCapabilities { .. } => "CH0019",
+            ChiruError::NoActiveScope => "CH0020",
+            ChiruError::UnknownScope { .. } => "CH0021",
+            ChiruError::ScopeNotEnclosing { .. } => "CH0022",
+            ChiruError::DuplicateDeclaration { .. } => "CH0023",
+            ChiruError::OutOfScope { .. } => "CH0024",
+            ChiruError::MissingThreadCapability { .. } => "CH0025",
+            ChiruError::DataRace { .. } => "CH0026",
+            ChiruError::UseAfterFree { .. } => "CH0027",
+            ChiruError::MissingCapability { .. } => "CH0028",
+            ChiruError::ExpiredLifetime { .. } => "CH0029",
+            ChiruError::WriteWithoutUniqueMut { .. } => "CH0030",
+            ChiruError::MoveTargetNotEnclosing { .. } => "CH0031",
+            ChiruError::UseAfterMove { .. } => "CH0032",
+            ChiruError::MoveNotOwned { .. } => "CH0033",
+            ChiruError::MoveWithActiveCapabilities { .. } => "CH0034",
+            ChiruError::UnknownCapability { .. } => "CH0035",
+            ChiruError::CapabilityNotHeld { .. } => "CH0036",
+            ChiruError::CapabilityNotLive { .. } => "CH0037",
+            ChiruError::InvalidDowngrade { .. } => "CH0038",
+            ChiruError::ReborrowExceedsParent { .. } => "CH0039",
+            ChiruError::ReborrowOutlivesParent { .. } => "CH0040",
+            ChiruError::CapabilitySuspended { .. } => "CH0041",
+            ChiruError::ReborrowOfOtherValue { .. } => "CH0042",
+            ChiruError::Leak { .. } => "CH0043",
+            ChiruError::ValueOutlivesRegion { .. } => "CH0044",
+            ChiruError::CapabilityOutlivesRegion { .. } => "CH0045",
+            ChiruError::InactiveRegion { .. } => "CH0046",
+            ChiruError::ExternalDrop { .. } => "CH0047",
+            ChiruError::ExternalNotReturned { .. } => "CH0048",
+            ChiruError::ReturnToWrongRegion { .. } => "CH0049",
+            ChiruError::ReturnNotExternal { .. } => "CH0050",
+            ChiruError::StaticDrop { .. } => "CH0051",
+            ChiruError::ExternalRegionOutsideUnsafe { .. } => "CH0052",
+            ChiruError::SecretNotZeroized { .. } => "CH0053",
+            ChiruError::InformationFlow { .. } => "CH0054",
+            ChiruError::DeclassifyOutsideUnsafe => "CH0055",
+            ChiruError::DeclassifyWithoutAssumption { .. } => "CH0056",
+            ChiruError::UnknownLabel { .. } => "CH0057",
+            ChiruError::UnknownFunction { .. } => "CH0058",
+            ChiruError::ArityMismatch { .. } => "CH0059",
+            ChiruError::MissingCallCapability { .. } => "CH0060",
+            ChiruError::AliasedArgument { .. } => "CH0061",
+            ChiruError::Recursion { .. } => "CH0062",
+            ChiruError::BranchInconsistency { .. } => "CH0063",
+            ChiruError::BranchPhaseMismatch { .. } => "CH0064",
+            ChiruError::LoopDivergence { .. } => "CH0065",
+            ChiruError::CapturedName { .. } => "CH0066",
+            ChiruError::BranchOwnerMismatch { .. } => "CH0067",
+            ChiruError::BranchReleaseMismatch { .. } => "CH0068",
+        }
+    }
+
+    /// The report summary category this error counts against.
+    pub fn kind(&self) -> ViolationKind {
+        match self {
+            ChiruError::UnknownValue { .. }
+            | ChiruError::UnknownRegion { .. }
+            | ChiruError::UnknownLifetime { .. }
+            | ChiruError::UnknownRegionKind { .. }
+            | ChiruError::UnknownCapabilityKind { .. }
+            | ChiruError::UnknownPhase { .. }
+            | ChiruError::DuplicatePhase { .. }
+            | ChiruError::PhaseNotAtModuleLevel { .. }
+            | ChiruError::PhaseDeclaredLate { .. }
+            | ChiruError::AssumptionOutsideUnsafe
+            | ChiruError::NoActiveScope
+            | ChiruError::UnknownCapability { .. }
+            | ChiruError::ExternalRegionOutsideUnsafe { .. }
+            | ChiruError::DeclassifyOutsideUnsafe
+            | ChiruError::UnknownLabel { .. }
+            | ChiruError::UnknownFunction { .. }
+            | ChiruError::ArityMismatch { .. }
+            | ChiruError::Recursion { .. }
+            | ChiruError::CapturedName { .. }
+            | ChiruError::DuplicateDeclaration { .. }
+            | ChiruError::OutOfScope { .. } => ViolationKind::Declarations,
+
+            ChiruError::PhaseRegression { .. }
+            | ChiruError::PhaseSkipped { .. }
+            | ChiruError::InactiveLifetime { .. }
+            | ChiruError::LifetimePhaseMismatch { .. }
+            | ChiruError::UnknownScope { .. }
+            | ChiruError::ScopeNotEnclosing { .. }
+            | ChiruError::ExpiredLifetime { .. }
+            | ChiruError::ReborrowOutlivesParent { .. }
+            | ChiruError::ValueOutlivesRegion { .. }
+            | ChiruError::CapabilityOutlivesRegion { .. }
+            | ChiruError::InactiveRegion { .. }
+            | ChiruError::BranchPhaseMismatch { .. } => ViolationKind::Lifetimes,
+
+            ChiruError::CapabilityConflict { .. }
+            | ChiruError::MissingThreadCapability { .. }
+            | ChiruError::DataRace { .. }
+            | ChiruError::MissingCapability { .. }
+            | ChiruError::WriteWithoutUniqueMut { .. }
+            | ChiruError::CapabilityNotHeld { .. }
+            | ChiruError::CapabilityNotLive { .. }
+            | ChiruError::InvalidDowngrade { .. }
+            | ChiruError::ReborrowExceedsParent { .. }
+            | ChiruError::CapabilitySuspended { .. }
+            | ChiruError::ReborrowOfOtherValue { .. }
+            | ChiruError::MissingCallCapability { .. }
+            | ChiruError::AliasedArgument { .. }
+            | ChiruError::BranchReleaseMismatch { .. } => ViolationKind::Capabilities,
+
+            ChiruError::DropNotOwned { .. }
+            | ChiruError::MoveTargetNotEnclosing { .. }
+            | ChiruError::UseAfterMove { .. }
+            | ChiruError::MoveNotOwned { .. }
+            | ChiruError::MoveWithActiveCapabilities { .. } => ViolationKind::Ownership,
+
+            ChiruError::CapabilityOnDestroyedValue { .. }
+            | ChiruError::DoubleDrop { .. }
+            | ChiruError::DropWithActiveCapabilities { .. }
+            | ChiruError::UseAfterFree { .. }
+            | ChiruError::Leak { .. }
+            | ChiruError::ExternalDrop { .. }
+            | ChiruError::ExternalNotReturned { .. }
+            | ChiruError::ReturnToWrongRegion { .. }
+            | ChiruError::ReturnNotExternal { .. }
+            | ChiruError::StaticDrop { .. }
+            | ChiruError::BranchInconsistency { .. }
+            | ChiruError::BranchOwnerMismatch { .. }
+            | ChiruError::LoopDivergence { .. } => ViolationKind::Destruction,
+
+            ChiruError::SecretNotZeroized { .. } => ViolationKind::Zeroization,
+
+            ChiruError::InformationFlow { .. }
+            | ChiruError::DeclassifyWithoutAssumption { .. } => ViolationKind::InformationFlow,
+        }
+    }
+}
+
+impl fmt::Display for ChiruError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChiruError::UnknownValue { value } => write!(f, "Unknown value: {}", value),
+            ChiruError::UnknownRegion { region } => write!(f, "Unknown region: {}", region),
+            ChiruError::UnknownLifetime { lifetime } => {
+                write!(f, "Unknown lifetime: {}", lifetime)
+            }
+            ChiruError::UnknownRegionKind { kind } => {
+                write!(f, "Unknown region kind: {}", kind)
+            }
+            ChiruError::UnknownCapabilityKind { kind } => {
+                write!(f, "Unknown capability kind: {}", kind)
+            }
+            ChiruError::UnknownPhase { phase } => write!(f, "Unknown phase: {}", phase),
+            ChiruError::DuplicatePhase { phase } => {
+                write!(f, "Duplicate phase declaration: {}", phase)
+            }
+            ChiruError::PhaseNotAtModuleLevel { phase } => {
+                write!(f, "Phase `{}` must be declared at module level", phase)
+            }
+            ChiruError::PhaseDeclaredLate { phase } => write!(
+                f,
+                "Phase `{}` must be declared before any other statement",
+                phase
+            ),
+            ChiruError::PhaseRegression { from, to } => write!(
+                f,
+                "Phase transition violation: cannot go back from `{}` to `{}`",
+                from, to
+            ),
+            ChiruError::PhaseSkipped { from, to, skipped } => {
+                let skipped: Vec<String> =
+                    skipped.iter().map(|p| format!("`{}`", p)).collect();
+                write!(
+                    f,
+                    "Phase transition violation: advancing from `{}` to `{}` skips {}",
+                    from,
+                    to,
+                    skipped.join(", ")
+                )
+            }
+            ChiruError::CapabilityConflict { value, held, requested } => write!(
+                f,
+                "Capability conflict on `{}`: {} requested while {} is live; \
+                 Own, UniqueMut and ThreadSend require exclusivity",
+                value, requested, held
+            ),
+            ChiruError::CapabilityOnDestroyedValue { value } => {
+                write!(f, "Cannot create capability for destroyed value `{}`", value)
+            }
+            ChiruError::InactiveLifetime { lifetime } => write!(
+                f,
+                "Cannot create capability with inactive lifetime `{}`",
+                lifetime
+            ),
+            ChiruError::LifetimePhaseMismatch { lifetime, created_in, used_in } => write!(
+                f,
+                "Lifetime phase violation: lifetime `{}` created in phase `{}`, used in phase `{}`",
+                lifetime, created_in, used_in
+            ),
+            ChiruError::AssumptionOutsideUnsafe => {
+                write!(f, "Unsafe assumptions must be declared inside unsafe scope")
+            }
+            ChiruError::DoubleDrop { value } => {
+                write!(f, "Attempted to drop value `{}` that is already destroyed", value)
+            }
+            ChiruError::DropNotOwned { value, scope } => write!(
+                f,
+                "Cannot drop value `{}`: it is not owned by {}",
+                value, scope
+            ),
+            ChiruError::DropWithActiveCapabilities { value } => write!(
+                f,
+                "Cannot drop value `{}` because active capabilities exist.\n\
+                 Rule: A value may only be destroyed after all capabilities \
+                 expire or are released.",
+                value
+            ),
+            ChiruError::NoActiveScope => write!(f, "No active scope"),
+            ChiruError::UnknownScope { lifetime, scope } => write!(
+                f,
+                "Lifetime `{}` is bound to unknown scope `{}`",
+                lifetime, scope
+            ),
+            ChiruError::ScopeNotEnclosing { lifetime, scope } => write!(
+                f,
+                "Lifetime `{}` is bound to `{}`, which does not enclose this statement",
+                lifetime, scope
+            ),
+            ChiruError::DuplicateDeclaration { kind, name } => write!(
+                f,
+                "Duplicate declaration: {} `{}` is already declared in this scope",
+                kind, name
+            ),
+            ChiruError::OutOfScope { kind, name } => write!(
+                f,
+                "{} `{}` is not in scope here: the scope that declared it has exited",
+                capitalize(kind), name
+            ),
+            ChiruError::MissingThreadCapability { value } => write!(
+                f,
+                "Value `{}` is used inside spawn without ThreadSend or ThreadShare",
+                value
+            ),
+            ChiruError::DataRace { value } => write!(
+                f,
+                "Data race on `{}`: concurrently live spawns access it and at least \
+                 one access is exclusive",
+                value
+            ),
+            ChiruError::UseAfterFree { value } => {
+                write!(f, "Use after free: `{}` has already been destroyed", value)
+            }
+            ChiruError::MissingCapability { value, access } => write!(
+                f,
+                "Cannot {} `{}`: no live capability grants access to it",
+                access, value
+            ),
+            ChiruError::ExpiredLifetime { value, lifetime } => write!(
+                f,
+                "Cannot access `{}`: its capability's lifetime `{}` has expired",
+                value, lifetime
+            ),
+            ChiruError::WriteWithoutUniqueMut { value, held } => write!(
+                f,
+                "Cannot write `{}`: writing requires UniqueMut, but only {} is held",
+                value,
+                held.join(", ")
+            ),
+            ChiruError::MoveTargetNotEnclosing { value, scope } => write!(
+                f,
+                "Cannot move `{}` to `{}`: it is not an enclosing scope",
+                value, scope
+            ),
+            ChiruError::UseAfterMove { value } => {
+                write!(f, "Use after move: `{}` has been moved out of this scope", value)
+            }
+            ChiruError::MoveNotOwned { value } => write!(
+                f,
+                "Cannot move `{}`: it is not owned by the current scope",
+                value
+            ),
+            ChiruError::MoveWithActiveCapabilities { value } => write!(
+                f,
+                "Cannot move `{}` because active capabilities exist",
+                value
+            ),
+            ChiruError::UnknownCapability { capability } => {
+                write!(f, "Unknown capability: {}", capability)
+            }
+            ChiruError::CapabilityNotHeld { value, kind } => write!(
+                f,
+                "No live {} capability on `{}`",
+                kind, value
+            ),
+            ChiruError::CapabilityNotLive { capability } => write!(
+                f,
+                "Capability `{}` is no longer live: it has been released or has expired",
+                capability
+            ),
+            ChiruError::InvalidDowngrade { value, from, to } => write!(
+                f,
+                "Cannot downgrade {} on `{}` to {}: {} does not include {}",
+                from, value, to, from, to
+            ),
+            ChiruError::ReborrowExceedsParent { value, parent, requested } => write!(
+                f,
+                "Cannot reborrow {} on `{}` from {}: a reborrow cannot grant more \
+                 than its parent",
+                requested, value, parent
+            ),
+            ChiruError::ReborrowOutlivesParent { value, lifetime, parent_lifetime } => write!(
+                f,
+                "Reborrow of `{}` during `{}` would outlive its parent's lifetime `{}`",
+                value, lifetime, parent_lifetime
+            ),
+            ChiruError::CapabilitySuspended { value, kind } => write!(
+                f,
+                "The {} capability on `{}` is suspended while a capability reborrowed \
+                 from it is live",
+                kind, value
+            ),
+            ChiruError::ReborrowOfOtherValue { value, parent_value } => write!(
+                f,
+                "Cannot reborrow `{}` from a capability on `{}`",
+                value, parent_value
+            ),
+            ChiruError::Leak { value, scope } => write!(
+                f,
+                "Memory leak: heap value `{}` allocated in {} is still owned when \
+                 its scope exits; drop it or move it out first",
+                value, scope
+            ),
+            ChiruError::ValueOutlivesRegion { value, region } => write!(
+                f,
+                "Dangling value: `{}` is still alive when its region `{}` goes out of scope",
+                value, region
+            ),
+            ChiruError::CapabilityOutlivesRegion { value, lifetime, region } => write!(
+                f,
+                "Capability on `{}` during `{}` would outlive the region `{}` it lives in",
+                value, lifetime, region
+            ),
+            ChiruError::InactiveRegion { region } => write!(
+                f,
+                "Cannot allocate in region `{}`: its scope has exited",
+                region
+            ),
+            ChiruError::ExternalDrop { value, region } => write!(
+                f,
+                "Cannot drop external value `{}`: external memory must be handed back \
+                 with `return {} to {}`",
+                value, value, region
+            ),
+            ChiruError::ExternalNotReturned { value, region } => write!(
+                f,
+                "External value `{}` was never returned to region `{}` before its \
+                 owner exited",
+                value, region
+            ),
+            ChiruError::ReturnToWrongRegion { value, region, allocated_in } => write!(
+                f,
+                "Cannot return `{}` to `{}`: it was allocated in `{}`",
+                value, region, allocated_in
+            ),
+            ChiruError::ReturnNotExternal { value, region } => write!(
+                f,
+                "Cannot return `{}` to `{}`: only external memory is returned; use `drop`",
+                value, region
+            ),
+            ChiruError::StaticDrop { value } => write!(
+                f,
+                "Cannot drop static value `{}`: static memory lives for the whole program",
+                value
+            ),
+            ChiruError::SecretNotZeroized { value } => write!(
+                f,
+                "Secret value `{}` is destroyed without being zeroized after its last write",
+                value
+            ),
+            ChiruError::InformationFlow { from, to, from_label, to_label } => write!(
+                f,
+                "Information flow violation: {} value `{}` flows into {} `{}`; \
+                 wrap the flow in `declassify` inside unsafe with an assumption",
+                from_label, from, to_label, to
+            ),
+            ChiruError::DeclassifyOutsideUnsafe => {
+                write!(f, "`declassify` must be used inside an unsafe block")
+            }
+            ChiruError::DeclassifyWithoutAssumption { from, to } => write!(
+                f,
+                "Cannot declassify `{}` into `{}`: the enclosing unsafe block states no \
+                 assumption",
+                from, to
+            ),
+            ChiruError::UnknownLabel { label } => write!(f, "Unknown security label: {}", label),
+            ChiruError::UnknownFunction { function } => {
+                write!(f, "Unknown function: {}", function)
+            }
+            ChiruError::ArityMismatch { function, expected, found } => write!(
+                f,
+                "`{}` takes {} argument(s) but the call passes {}",
+                function, expected, found
+            ),
+            ChiruError::MissingCallCapability { function, value, required } => write!(
+                f,
+                "Cannot pass `{}` to `{}`: the parameter requires {}, but no usable \
+                 capability held on `{}` includes it",
+                value, function, required, value
+            ),
+            ChiruError::AliasedArgument { function, value } => write!(
+                f,
+                "`{}` is passed to `{}` more than once, and one of its parameters \
+                 requires exclusive access",
+                value, function
+            ),
+            ChiruError::Recursion { cycle } => write!(
+                f,
+                "Recursive call: {}; a function's summary is only complete once its \
+                 body has been verified",
+                cycle.join(" -> ")
+            ),
+            ChiruError::BranchInconsistency { value, action, path } => write!(
+                f,
+                "Inconsistent branches: `{}` is {} on the {} path but not on the other; \
+                 both paths must leave it in the same state",
+                value, action, path
+            ),
+            ChiruError::BranchOwnerMismatch { value, owner, path } => write!(
+                f,
+                "Inconsistent branches: `{}` is handed to {} on the {} path but not on \
+                 the other; both paths must leave it with the same owner",
+                value, owner, path
+            ),
+            ChiruError::BranchReleaseMismatch { kind, value, path } => write!(
+                f,
+                "Inconsistent branches: the {} capability on `{}` is released on the {} \
+                 path but not on the other; both paths must leave the same capabilities held",
+                kind, value, path
+            ),
+            ChiruError::LoopDivergence { iteration, values } => write!(
+                f,
+                "Loop diverges at iteration {}: values allocated in the body pile up \
+                 ({}); drop them or hand them off before the iteration ends",
+                iteration,
+                values.join(", ")
+            ),
+            ChiruError::BranchPhaseMismatch { then_phase, else_phase } => write!(
+                f,
+                "Inconsistent branches: the `if` path ends in phase `{}` but the `else` \
+                 path in `{}`",
+                then_phase, else_phase
+            ),
+            ChiruError::CapturedName { kind, name, function } => write!(
+                f,
+                "Function `{}` cannot use {} `{}` declared outside it; a function body \
+                 only sees the values its parameters pass in",
+                function, kind, name
+            ),
+            ChiruError::ExternalRegionOutsideUnsafe { region } => write!(
+                f,
+                "External region `{}` must be declared inside an unsafe block or with an \
+                 assumption: `region external {} assume \"...\"`",
+                region, region
+            ),
+        }
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -1,160 +1,2372 @@
-use crate::ast::AstNode;
-use crate::interpreter::InterpreterState;
-use crate::scope::ScopeKind;
-use crate::graph::{RegionKind, CapabilityKind};
-
-use std::collections::HashMap;
-
-pub struct ExecContext {
-    pub lifetimes: HashMap<String, u64>,
-    pub regions: HashMap<String, u64>,
-    pub values: HashMap<String, u64>,
-}
-
-impl ExecContext {
-    pub fn new() -> Self {
-        ExecContext {
-            lifetimes: HashMap::new(),
-            regions: HashMap::new(),
-            values: HashMap::new(),
-        }
-    }
-}
-
-pub fn execute(
-    nodes: &[AstNode],
-    state: &mut InterpreterState,
-    ctx: &mut ExecContext,
-) -> Result<(), String> {
-    for node in nodes {
-        match node {
-            // --------------------------------------------------
-            // PHASE DECLARATION (module-level only)
-            // --------------------------------------------------
-            AstNode::PhaseDecl { name } => {
-                state.declare_phase(name.clone())?;
-            }
-
-            // --------------------------------------------------
-            // FUNCTION
-            // --------------------------------------------------
-            AstNode::Function { name: _, body } => {
-                state.enter_scope(ScopeKind::Function);
-                execute(body, state, ctx)?;
-                state.exit_scope()?;
-            }
-
-            // --------------------------------------------------
-            // UNSAFE BLOCK
-            // --------------------------------------------------
-            AstNode::Unsafe { body } => {
-                state.enter_scope(ScopeKind::Unsafe);
-                execute(body, state, ctx)?;
-                state.exit_scope()?;
-            }
-
-            // --------------------------------------------------
-            // REGION DECLARATION
-            // --------------------------------------------------
-            AstNode::Region { kind, name } => {
-                let k = match kind.as_str() {
-                    "heap" => RegionKind::Heap,
-                    "stack" => RegionKind::Stack,
-                    "external" => RegionKind::External,
-                    "static" => RegionKind::Static,
-                    _ => return Err(format!("Unknown region kind: {}", kind)),
-                };
-
-                let id = state.declare_region(k)?;
-                ctx.regions.insert(name.clone(), id);
-            }
-
-            // --------------------------------------------------
-            // LIFETIME DECLARATION (phase-bound implicitly)
-            // --------------------------------------------------
-            AstNode::Lifetime { name, scope: _ } => {
-                let scope_id = state.current_scope();
-                let phase_id = state.current_phase();
-
-                let id = state.create_lifetime(scope_id, phase_id)?;
-                ctx.lifetimes.insert(name.clone(), id);
-            }
-
-            // --------------------------------------------------
-            // VALUE ALLOCATION
-            // --------------------------------------------------
-            AstNode::Let { name, region } => {
-                let region_id = ctx
-                    .regions
-                    .get(region)
-                    .ok_or(format!("Unknown region: {}", region))?;
-
-                let value_id = state.allocate_value(*region_id)?;
-                ctx.values.insert(name.clone(), value_id);
-            }
-
-            // --------------------------------------------------
-            // CAPABILITY GRANT (phase-enforced)
-            // --------------------------------------------------
-            AstNode::Capability {
-                kind,
-                value,
-                lifetime,
-            } => {
-                let cap_kind = match kind.as_str() {
-                    "Own" => CapabilityKind::Own,
-                    "SharedRead" => CapabilityKind::SharedRead,
-                    "UniqueMut" => CapabilityKind::UniqueMut,
-                    _ => return Err(format!("Unknown capability kind: {}", kind)),
-                };
-
-                let value_id = ctx
-                    .values
-                    .get(value)
-                    .ok_or(format!("Unknown value: {}", value))?;
-
-                let lifetime_id = ctx
-                    .lifetimes
-                    .get(lifetime)
-                    .ok_or(format!("Unknown lifetime: {}", lifetime))?;
-
-                let phase_id = state.current_phase();
-
-                state.create_capability(
-                    cap_kind,
-                    *value_id,
-                    *lifetime_id,
-                    phase_id,
-                )?;
-            }
-
-            // --------------------------------------------------
-            // DROP VALUE (capability + phase checked internally)
-            // --------------------------------------------------
-            AstNode::Drop { value } => {
-                let value_id = ctx
-                    .values
-                    .get(value)
-                    .ok_or(format!("Unknown value: {}", value))?;
-
-                state.drop_value(*value_id)?;
-            }
-
-            // --------------------------------------------------
-            // UNSAFE ASSUMPTION (phase-bound)
-            // --------------------------------------------------
-            AstNode::Assume { text } => {
-                let affected: Vec<u64> = ctx.values.values().copied().collect();
-                let phase_id = state.current_phase();
-
-                state.add_unsafe_assumption(
-                    text.clone(),
-                    phase_id,
-                    affected,
-                )?;
-            }
-        }
-    }
-
-    Ok(())
-}
+use crate::ast::{AstNode, CapabilityRef, NodeKind, Span};
+use crate::interpreter::{Access, InterpreterState, Joined};
+use crate::scope::ScopeKind;
+use crate::graph::{
+    AssumptionId, CapabilityId, CapabilityKind, Label, RegionKind, ScopeId, ValueId,
+};
+use crate::error::ChiruError;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Namespace {
+    Lifetime,
+    Region,
+    Value,
+    Capability,
+    Function,
+}
+
+impl fmt::Display for Namespace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Namespace::Lifetime => write!(f, "lifetime"),
+            Namespace::Region => write!(f, "region"),
+            Namespace::Value => write!(f, "value"),
+            Namespace::Capability => write!(f, "capability"),
+            Namespace::Function => write!(f, "function"),
+        }
+    }
+}
+
+/// The names declared directly in one interpreter scope.
+#[derive(Clone)]
+struct SymbolScope {
+    scope: ScopeId,
+    names: HashMap<(Namespace, String), u64>,
+    /// Values moved out of this scope; their names stay unusable here.
+    moved: HashSet<String>,
+    /// Values, regions and lifetimes whose declaration failed. Uses of them
+    /// are not reported again.
+    poisoned: HashSet<(Namespace, String)>,
+}
+
+/// What a function does with a parameter it takes by Own. The caller's
+/// argument goes the same way at every call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Discharge {
+    /// Still owned by the function when its body ends.
+    Kept,
+    /// Dropped; `zeroized` when it was wiped first.
+    Dropped { zeroized: bool },
+    /// Moved out to a scope enclosing the function.
+    Moved(ScopeId),
+}
+
+impl fmt::Display for Discharge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Discharge::Kept => write!(f, "kept"),
+            Discharge::Dropped { .. } => write!(f, "dropped"),
+            Discharge::Moved(_) => write!(f, "moved out"),
+        }
+    }
+}
+
+/// A value a function body allocates and moves out to `target`, a scope
+/// enclosing the function. `value` stands for the new value each call
+/// leaves there; it is not itself part of the program's state.
+#[derive(Debug, Clone)]
+pub struct Escape {
+    pub name: String,
+    pub value: ValueId,
+    pub target: ScopeId,
+    /// The assumptions each value the call creates rests on.
+    pub assumptions: Vec<AssumptionId>,
+}
+
+/// What a function needs from its callers and what calling it entails,
+/// computed once when its body is verified and applied at every call.
+#[derive(Debug, Clone)]
+pub struct FunctionSummary {
+    pub name: String,
+    /// Each parameter and the capability a caller must hold on its argument.
+    pub params: Vec<(String, CapabilityKind)>,
+    /// Parameters taken by Own and what the body does with each.
+    pub consumes: Vec<(String, Discharge)>,
+    /// Values the body moves out to a scope enclosing the function.
+    pub moves: Vec<Escape>,
+    /// Parameters the body writes, itself or through its calls; each call
+    /// writes the arguments passed to them.
+    pub writes: Vec<String>,
+    /// Phases the body advances to, itself or through its calls, in order;
+    /// each call advances through them from the caller's phase.
+    pub advances: Vec<String>,
+    /// Flows from one parameter into another outside `declassify`, as
+    /// (from, into); each call checks them against its arguments' labels.
+    pub flows: Vec<(String, String)>,
+    pub calls: Vec<String>,
+    /// Called functions that are unsafe themselves or through their calls;
+    /// a caller of an unsafe function is unsafe too.
+    pub unsafe_calls: Vec<String>,
+    /// Assumptions stated in the body itself.
+    pub assumptions: Vec<AssumptionId>,
+    /// `assumptions` and those of every function called, transitively.
+    pub depends_on: Vec<AssumptionId>,
+    /// Violations found in the body, not counting nested functions.
+    pub violations: usize,
+}
+
+/// A function whose body is being verified and whose summary is not
+/// complete yet.
+#[derive(Clone)]
+struct Verifying {
+    id: u64,
+    scope: ScopeId,
+    summary: FunctionSummary,
+    /// Index of the first violation recorded after the body was entered.
+    first_violation: usize,
+    /// Violations recorded in the bodies of nested functions.
+    nested_violations: usize,
+    /// The values bound to the parameters, in `summary.params` order.
+    bound: Vec<ValueId>,
+    /// The values bound to Own parameters, in `summary.consumes` order,
+    /// and how each has been discharged so far.
+    owned: Vec<ValueId>,
+    discharged: HashMap<ValueId, Discharge>,
+}
+
+/// Lexically scoped name resolution.
+///
+/// `frames` mirrors `InterpreterState::scope_stack`: a name is visible in
+/// the scope that declares it and every scope nested inside it. Inner
+/// declarations shadow outer ones; redeclaring in the same scope is an error.
+#[derive(Clone)]
+pub struct ExecContext {
+    frames: Vec<SymbolScope>,
+    /// Names whose declaring scope has exited, for better diagnostics.
+    retired: HashSet<(Namespace, String)>,
+    next_function_id: u64,
+    /// Summaries of the functions verified so far, by function id.
+    summaries: HashMap<u64, FunctionSummary>,
+    /// Functions being verified, innermost last.
+    verifying: Vec<Verifying>,
+    /// Declared functions whose bodies have not been verified yet, with
+    /// the scope declaring them.
+    pending: HashMap<u64, (ScopeId, AstNode)>,
+    /// Function ids by declaration, so that a declaration reached again,
+    /// as in a loop, is not verified again.
+    declared: HashMap<Span, u64>,
+}
+
+impl ExecContext {
+    pub fn new() -> Self {
+        ExecContext {
+            frames: Vec::new(),
+            retired: HashSet::new(),
+            next_function_id: 1,
+            summaries: HashMap::new(),
+            verifying: Vec::new(),
+            pending: HashMap::new(),
+            declared: HashMap::new(),
+        }
+    }
+
+    pub fn push_scope(&mut self, scope: ScopeId) {
+        self.frames.push(SymbolScope {
+            scope,
+            names: HashMap::new(),
+            moved: HashSet::new(),
+            poisoned: HashSet::new(),
+        });
+    }
+
+    pub fn pop_scope(&mut self) {
+        if let Some(frame) = self.frames.pop() {
+            self.retired.extend(frame.names.into_keys());
+        }
+    }
+
+    /// Continue function ids where `other` left off; see
+    /// `InterpreterState::continue_ids_from`.
+    pub fn continue_ids_from(&mut self, other: &ExecContext) {
+        self.next_function_id = other.next_function_id;
+    }
+
+    /// Join the name bindings of the two paths of an `if`, forked from
+    /// `self`, as `joined` joined their states. Both paths leave the same
+    /// frames behind; a name declared or moved on either path is declared
+    /// or moved afterwards, unless it names a value created on that path
+    /// only.
+    pub fn join(&mut self, then_ctx: ExecContext, else_ctx: ExecContext, joined: &Joined) {
+        let base = std::mem::replace(self, then_ctx);
+        let usable = |key: &(Namespace, String), id: &u64| {
+            key.0 != Namespace::Value || !joined.one_sided.contains(id)
+        };
+        for (frame, other) in self.frames.iter_mut().zip(else_ctx.frames) {
+            frame.names.retain(|key, id| usable(key, id));
+            for (key, id) in other.names {
+                let id = match key.0 {
+                    Namespace::Value => joined.merged.get(&id).copied().unwrap_or(id),
+                    _ => id,
+                };
+                if usable(&key, &id) {
+                    frame.names.entry(key).or_insert(id);
+                }
+            }
+            frame.moved.extend(other.moved);
+            frame.poisoned.extend(other.poisoned);
+        }
+        self.retired.extend(else_ctx.retired);
+        for (id, summary) in else_ctx.summaries {
+            self.summaries.entry(id).or_insert(summary);
+        }
+        self.pending.retain(|id, _| else_ctx.pending.contains_key(id));
+        self.declared.extend(else_ctx.declared);
+        self.next_function_id = else_ctx.next_function_id;
+
+        // Summaries still being collected cover both paths
+        let pending = self.verifying.iter_mut().zip(else_ctx.verifying).zip(base.verifying);
+        for ((function, other), before) in pending {
+            let summary = &mut function.summary;
+            for call in other.summary.calls {
+                if !summary.calls.contains(&call) {
+                    summary.calls.push(call);
+                }
+            }
+            for call in other.summary.unsafe_calls {
+                if !summary.unsafe_calls.contains(&call) {
+                    summary.unsafe_calls.push(call);
+                }
+            }
+            summary.assumptions.extend(other.summary.assumptions);
+            summary.assumptions.sort();
+            summary.assumptions.dedup();
+            summary.depends_on.extend(other.summary.depends_on);
+            for param in other.summary.writes {
+                if !summary.writes.contains(&param) {
+                    summary.writes.push(param);
+                }
+            }
+            for flow in other.summary.flows {
+                if !summary.flows.contains(&flow) {
+                    summary.flows.push(flow);
+                }
+            }
+            function.nested_violations += other.nested_violations - before.nested_violations;
+            for (value, discharge) in other.discharged {
+                function.discharged.entry(value).or_insert(discharge);
+            }
+        }
+    }
+
+    fn frame_mut(&mut self, scope: ScopeId) -> &mut SymbolScope {
+        self.frames.iter_mut()
+            .rev()
+            .find(|f| f.scope == scope)
+            .expect("Symbol scope out of sync with interpreter scope stack")
+    }
+
+    /// Fail if `name` is already declared directly in `scope`.
+    /// Called before the interpreter creates the node, so a rejected
+    /// redeclaration leaves no trace in the graph.
+    pub fn ensure_undeclared(
+        &mut self,
+        scope: ScopeId,
+        ns: Namespace,
+        name: &str,
+    ) -> Result<(), ChiruError> {
+        if self.frame_mut(scope).names.contains_key(&(ns, name.to_string())) {
+            return Err(ChiruError::DuplicateDeclaration {
+                kind: ns.to_string(),
+                name: name.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn is_bound(&mut self, scope: ScopeId, ns: Namespace, name: &str, id: u64) -> bool {
+        self.frame_mut(scope).names.get(&(ns, name.to_string())) == Some(&id)
+    }
+
+    /// Declare a name in `scope`, which is the innermost scope or, for a
+    /// lifetime bound to an outer function, an enclosing one.
+    pub fn declare(&mut self, scope: ScopeId, ns: Namespace, name: &str, id: u64) {
+        let frame = self.frame_mut(scope);
+        if ns == Namespace::Value {
+            frame.moved.remove(name);
+        }
+        frame.poisoned.remove(&(ns, name.to_string()));
+        frame.names.insert((ns, name.to_string()), id);
+    }
+
+    /// Remove the binding of `name` in `scope` if it is to `id`.
+    pub fn unbind(&mut self, scope: ScopeId, name: &str, id: u64) {
+        let frame = self.frame_mut(scope);
+        let key = (Namespace::Value, name.to_string());
+        if frame.names.get(&key) == Some(&id) {
+            frame.names.remove(&key);
+        }
+    }
+
+    /// Rebind a moved value from `from` to `to`, leaving a tombstone in
+    /// `from` so later uses there are reported as use-after-move.
+    pub fn move_binding(&mut self, from: ScopeId, to: ScopeId, name: &str, id: u64) {
+        let frame = self.frame_mut(from);
+        frame.names.remove(&(Namespace::Value, name.to_string()));
+        frame.moved.insert(name.to_string());
+        self.declare(to, Namespace::Value, name, id);
+    }
+
+    /// Drop the binding of a value passed by Own to a callee, leaving a
+    /// tombstone like a move does in the frame that bound it.
+    pub fn consume_binding(&mut self, name: &str) {
+        let key = (Namespace::Value, name.to_string());
+        if let Some(frame) = self.frames.iter_mut().rev().find(|f| f.names.contains_key(&key)) {
+            frame.names.remove(&key);
+            frame.moved.insert(name.to_string());
+        }
+    }
+
+    /// Mark `name` as a name of `scope` whose declaration failed, unless
+    /// the failure was that `scope` already declares it.
+    pub fn poison(&mut self, scope: ScopeId, ns: Namespace, name: &str) {
+        let frame = self.frame_mut(scope);
+        let key = (ns, name.to_string());
+        if !frame.names.contains_key(&key) {
+            frame.poisoned.insert(key);
+        }
+    }
+
+    /// Whether `error` only reports a use of a value, region or lifetime
+    /// whose declaration failed, which was reported already.
+    pub fn is_poisoned(&self, error: &ChiruError) -> bool {
+        let key = match error {
+            ChiruError::UnknownValue { value } => (Namespace::Value, value.clone()),
+            ChiruError::UnknownRegion { region } => (Namespace::Region, region.clone()),
+            ChiruError::UnknownLifetime { lifetime } => (Namespace::Lifetime, lifetime.clone()),
+            _ => return false,
+        };
+        let moved = |f: &SymbolScope| key.0 == Namespace::Value && f.moved.contains(&key.1);
+        self.frames.iter()
+            .rev()
+            .find(|f| f.names.contains_key(&key) || moved(f) || f.poisoned.contains(&key))
+            .is_some_and(|f| f.poisoned.contains(&key))
+    }
+
+    /// Declare the function `declaration` in `scope`; its body is verified
+    /// later, at the declaration or at a call made before it. A
+    /// declaration declared before keeps its id and summary.
+    pub fn declare_function(&mut self, scope: ScopeId, declaration: &AstNode) {
+        let NodeKind::Function { name, .. } = &declaration.kind else {
+            unreachable!("Only function declarations are declared as functions");
+        };
+        let id = match self.declared.get(&declaration.span) {
+            Some(id) => *id,
+            None => {
+                let id = self.next_function_id;
+                self.next_function_id += 1;
+                self.declared.insert(declaration.span, id);
+                id
+            }
+        };
+        self.declare(scope, Namespace::Function, name, id);
+        if !self.summaries.contains_key(&id) {
+            self.pending.insert(id, (scope, declaration.clone()));
+        }
+    }
+
+    /// Take function `id` off the pending list if its body has not been
+    /// verified, and, given a `span`, only if it was declared there.
+    pub fn take_pending(&mut self, id: u64, span: Option<Span>) -> Option<(ScopeId, AstNode)> {
+        let (_, declaration) = self.pending.get(&id)?;
+        if span.is_some_and(|span| declaration.span != span) {
+            return None;
+        }
+        self.pending.remove(&id)
+    }
+
+    /// Hide the frames from `depth` on, for code that runs in an
+    /// enclosing scope; `restore_frames` puts them back.
+    fn hide_frames(&mut self, depth: usize) -> Vec<SymbolScope> {
+        self.frames.split_off(depth)
+    }
+
+    fn restore_frames(&mut self, frames: Vec<SymbolScope>) {
+        self.frames.extend(frames);
+    }
+
+    /// Start collecting the summary of function `id`, whose body runs in
+    /// `scope` with its parameters bound to `values`.
+    pub fn begin_summary(
+        &mut self,
+        id: u64,
+        scope: ScopeId,
+        name: &str,
+        params: Vec<(String, CapabilityKind)>,
+        values: &[ValueId],
+        first_violation: usize,
+    ) {
+        let (consumes, owned) = params.iter()
+            .zip(values)
+            .filter(|((_, kind), _)| *kind == CapabilityKind::Own)
+            .map(|((param, _), value)| ((param.clone(), Discharge::Kept), *value))
+            .unzip();
+
+        self.verifying.push(Verifying {
+            id,
+            scope,
+            summary: FunctionSummary {
+                name: name.to_string(),
+                params,
+                consumes,
+                moves: Vec::new(),
+                writes: Vec::new(),
+                advances: Vec::new(),
+                flows: Vec::new(),
+                calls: Vec::new(),
+                unsafe_calls: Vec::new(),
+                assumptions: Vec::new(),
+                depends_on: Vec::new(),
+                violations: 0,
+            },
+            first_violation,
+            nested_violations: 0,
+            bound: values.to_vec(),
+            owned,
+            discharged: HashMap::new(),
+        });
+    }
+
+    /// Complete the innermost summary once its body has been verified and
+    /// `violations` have been recorded in total.
+    pub fn finish_summary(&mut self, violations: usize) {
+        let frame = self.verifying.pop().expect("No function being verified");
+        let total = violations - frame.first_violation;
+
+        let mut summary = frame.summary;
+        summary.violations = total - frame.nested_violations;
+        for ((_, discharge), value) in summary.consumes.iter_mut().zip(&frame.owned) {
+            if let Some(noted) = frame.discharged.get(value) {
+                *discharge = *noted;
+            }
+        }
+        summary.depends_on.extend(summary.assumptions.iter().copied());
+        summary.depends_on.sort();
+        summary.depends_on.dedup();
+
+        if let Some(parent) = self.verifying.last_mut() {
+            parent.nested_violations += total;
+        }
+        self.summaries.insert(frame.id, summary);
+    }
+
+    /// The summary to apply at a call of function `id`. A function whose
+    /// summary is still being computed is being called recursively.
+    pub fn summary(&self, id: u64) -> Result<&FunctionSummary, ChiruError> {
+        if let Some(summary) = self.summaries.get(&id) {
+            return Ok(summary);
+        }
+
+        let start = self.verifying.iter()
+            .position(|f| f.id == id)
+            .expect("Function neither verified nor being verified");
+        let mut cycle: Vec<String> = self.verifying[start..].iter()
+            .map(|f| f.summary.name.clone())
+            .collect();
+        cycle.push(self.verifying[start].summary.name.clone());
+        Err(ChiruError::Recursion { cycle })
+    }
+
+    /// Every completed summary, in declaration order.
+    pub fn summaries(&self) -> Vec<&FunctionSummary> {
+        let mut ids: Vec<&u64> = self.summaries.keys().collect();
+        ids.sort();
+        ids.into_iter().map(|id| &self.summaries[id]).collect()
+    }
+
+    /// The scope of the innermost function being verified.
+    pub fn function_scope(&self) -> Option<ScopeId> {
+        self.verifying.last().map(|f| f.scope)
+    }
+
+    pub fn note_call(&mut self, callee: &FunctionSummary) {
+        if let Some(frame) = self.verifying.last_mut() {
+            if !frame.summary.calls.contains(&callee.name) {
+                frame.summary.calls.push(callee.name.clone());
+            }
+            let unsafe_callee = callee.violations > 0 || !callee.unsafe_calls.is_empty();
+            if unsafe_callee && !frame.summary.unsafe_calls.contains(&callee.name) {
+                frame.summary.unsafe_calls.push(callee.name.clone());
+            }
+            frame.summary.depends_on.extend(callee.depends_on.iter().copied());
+        }
+    }
+
+    /// Record a write of `value`, if it is bound to one of the innermost
+    /// function's parameters.
+    pub fn note_write(&mut self, value: ValueId) {
+        let Some(frame) = self.verifying.last_mut() else {
+            return;
+        };
+        let param = frame.bound.iter()
+            .position(|v| *v == value)
+            .map(|i| &frame.summary.params[i].0);
+        if let Some(param) = param.filter(|p| !frame.summary.writes.contains(p)) {
+            frame.summary.writes.push(param.clone());
+        }
+    }
+
+    /// Record a flow of `sources`, a value and those it was derived from,
+    /// into `value`, for each of them bound to a parameter of the innermost
+    /// function if `value` is too.
+    pub fn note_flow(&mut self, sources: &[ValueId], value: ValueId) {
+        let Some(frame) = self.verifying.last_mut() else {
+            return;
+        };
+        let param = |v: &ValueId| {
+            frame.bound.iter().position(|b| b == v).map(|i| frame.summary.params[i].0.clone())
+        };
+        let Some(into) = param(&value) else {
+            return;
+        };
+        let flows: Vec<(String, String)> = sources.iter()
+            .filter_map(param)
+            .filter(|from| *from != into)
+            .map(|from| (from, into.clone()))
+            .collect();
+        for flow in flows {
+            if !frame.summary.flows.contains(&flow) {
+                frame.summary.flows.push(flow);
+            }
+        }
+    }
+
+    pub fn note_advance(&mut self, phase: &str) {
+        if let Some(frame) = self.verifying.last_mut() {
+            frame.summary.advances.push(phase.to_string());
+        }
+    }
+
+    /// The scope of the innermost function being verified, if its body has
+    /// not changed phase so far.
+    pub fn unadvanced_function(&self) -> Option<ScopeId> {
+        self.verifying.last().filter(|f| f.summary.advances.is_empty()).map(|f| f.scope)
+    }
+
+    pub fn note_move(&mut self, escape: Escape) {
+        if let Some(frame) = self.verifying.last_mut() {
+            frame.summary.moves.push(escape);
+        }
+    }
+
+    /// Record how `value` leaves the innermost function, if it is bound to
+    /// one of its Own parameters.
+    pub fn note_discharge(&mut self, value: ValueId, discharge: Discharge) {
+        let owner = self.verifying.last_mut().filter(|f| f.owned.contains(&value));
+        if let Some(frame) = owner {
+            frame.discharged.insert(value, discharge);
+        }
+    }
+
+    /// Refer to `earlier` wherever the summaries being collected refer to
+    /// `repeated`, an assumption a loop iteration stated again.
+    pub fn rename_assumption(&mut self, repeated: AssumptionId, earlier: AssumptionId) {
+        let rename = |ids: &mut Vec<AssumptionId>| {
+            if ids.contains(&repeated) {
+                ids.retain(|id| *id != repeated);
+                ids.push(earlier);
+                ids.sort();
+                ids.dedup();
+            }
+        };
+        for frame in self.verifying.iter_mut() {
+            rename(&mut frame.summary.assumptions);
+            rename(&mut frame.summary.depends_on);
+            for escape in frame.summary.moves.iter_mut() {
+                rename(&mut escape.assumptions);
+            }
+        }
+    }
+
+    pub fn note_assumption(&mut self, id: AssumptionId) {
+        if let Some(frame) = self.verifying.last_mut() {
+            frame.summary.assumptions.push(id);
+        }
+    }
+
+    pub fn lookup(&self, ns: Namespace, name: &str) -> Result<u64, ChiruError> {
+        let key = (ns, name.to_string());
+
+        // A function body sees values and capabilities through its
+        // parameters only; its summary could not account for others
+        let function = match ns {
+            Namespace::Value | Namespace::Capability => self.verifying.last(),
+            _ => None,
+        };
+
+        let mut frames = self.frames.iter().rev();
+        for frame in frames.by_ref() {
+            if let Some(id) = frame.names.get(&key) {
+                return Ok(*id);
+            }
+            if ns == Namespace::Value && frame.moved.contains(name) {
+                return Err(ChiruError::UseAfterMove { value: name.to_string() });
+            }
+            if frame.poisoned.contains(&key) {
+                return Err(unknown_name(ns, name));
+            }
+            if function.is_some_and(|f| f.scope == frame.scope) {
+                break;
+            }
+        }
+
+        if let Some(function) = function.filter(|_| frames.any(|f| f.names.contains_key(&key))) {
+            return Err(ChiruError::CapturedName {
+                kind: ns.to_string(),
+                name: name.to_string(),
+                function: function.summary.name.clone(),
+            });
+        }
+
+        if self.retired.contains(&key) {
+            return Err(ChiruError::OutOfScope {
+                kind: ns.to_string(),
+                name: name.to_string(),
+            });
+        }
+
+        Err(unknown_name(ns, name))
+    }
+}
+
+fn unknown_name(ns: Namespace, name: &str) -> ChiruError {
+    let name = name.to_string();
+    match ns {
+        Namespace::Lifetime => ChiruError::UnknownLifetime { lifetime: name },
+        Namespace::Region => ChiruError::UnknownRegion { region: name },
+        Namespace::Value => ChiruError::UnknownValue { value: name },
+        Namespace::Capability => ChiruError::UnknownCapability { capability: name },
+        Namespace::Function => ChiruError::UnknownFunction { function: name },
+    }
+}
+
+fn capability_kind(kind: &str) -> Result<CapabilityKind, ChiruError> {
+    match kind {
+        "Own" => Ok(CapabilityKind::Own),
+        "SharedRead" => Ok(CapabilityKind::SharedRead),
+        "UniqueMut" => Ok(CapabilityKind::UniqueMut),
+        "ThreadSend" => Ok(CapabilityKind::ThreadSend),
+        "ThreadShare" => Ok(CapabilityKind::ThreadShare),
+        _ => Err(ChiruError::UnknownCapabilityKind { kind: kind.to_string() }),
+    }
+}
+
+/// Find the live capability a `CapabilityRef` refers to.
+fn resolve_capability(
+    target: &CapabilityRef,
+    state: &InterpreterState,
+    ctx: &ExecContext,
+) -> Result<CapabilityId, ChiruError> {
+    match target {
+        CapabilityRef::Named(name) => {
+            let id = ctx.lookup(Namespace::Capability, name)?;
+            if !state.graph.capabilities.contains_key(&id) {
+                return Err(ChiruError::CapabilityNotLive { capability: name.clone() });
+            }
+            Ok(id)
+        }
+        CapabilityRef::Kind { kind, value } => {
+            let cap_kind = capability_kind(kind)?;
+            let value_id = ctx.lookup(Namespace::Value, value)?;
+            state.find_capability(value_id, cap_kind).ok_or_else(|| {
+                ChiruError::CapabilityNotHeld {
+                    value: value.clone(),
+                    kind: kind.clone(),
+                }
+            })
+        }
+    }
+}
+
+fn enter_scope(
+    state: &mut InterpreterState,
+    ctx: &mut ExecContext,
+    kind: ScopeKind,
+    name: Option<String>,
+) -> ScopeId {
+    let id = state.enter_scope(kind, name);
+    ctx.push_scope(id);
+    id
+}
+
+/// Exit the innermost scope. Problems found in what the scope leaves
+/// behind, such as leaked heap values, are attributed to `statement`.
+fn exit_scope(
+    state: &mut InterpreterState,
+    ctx: &mut ExecContext,
+    statement: &str,
+    span: Span,
+) -> Result<(), ChiruError> {
+    ctx.pop_scope();
+    for error in state.exit_scope()? {
+        state.record_violation(error, statement.to_string(), span);
+    }
+    Ok(())
+}
+
+/// Forget the violations recorded since `seen` that an earlier iteration
+/// of the same loop, starting at `first`, already recorded.
+fn forget_repeated_violations(state: &mut InterpreterState, first: usize, seen: usize) {
+    let (earlier, latest) = state.violations.split_at(seen);
+    let earlier = &earlier[first..];
+    let fresh: Vec<_> = latest.iter()
+        .filter(|v| {
+            !earlier.iter().any(|e| e.code == v.code && e.span == v.span && e.message == v.message)
+        })
+        .cloned()
+        .collect();
+    state.violations.truncate(seen);
+    state.violations.extend(fresh);
+}
+
+/// Forget the assumptions stated since those in `seen` that an earlier
+/// iteration of the same loop, which started with those in `first`,
+/// already stated.
+fn forget_repeated_assumptions(
+    state: &mut InterpreterState,
+    ctx: &mut ExecContext,
+    first: &HashSet<AssumptionId>,
+    seen: &HashSet<AssumptionId>,
+) {
+    let mut latest: Vec<AssumptionId> = state.graph.unsafe_assumptions.keys()
+        .filter(|id| !seen.contains(id))
+        .copied()
+        .collect();
+    latest.sort();
+    for id in latest.into_iter().rev() {
+        let node = &state.graph.unsafe_assumptions[&id];
+        let earlier = state.graph.unsafe_assumptions.values()
+            .filter(|e| seen.contains(&e.id) && !first.contains(&e.id))
+            .find(|e| e.span == node.span && e.description == node.description)
+            .map(|e| e.id);
+        if let Some(earlier) = earlier {
+            state.merge_assumption(id, earlier);
+            ctx.rename_assumption(id, earlier);
+        }
+    }
+}
+
+/// The entries of `now` not matched by an entry of `before`, counting
+/// duplicates.
+fn added(now: &[String], before: &[String]) -> Vec<String> {
+    let mut remaining = before.to_vec();
+    let mut added = Vec::new();
+    for item in now {
+        match remaining.iter().position(|r| r == item) {
+            Some(i) => {
+                remaining.remove(i);
+            }
+            None => added.push(item.clone()),
+        }
+    }
+    added
+}
+
+/// Execute one path of an `if`, or one loop iteration, in a block scope
+/// of its own.
+fn execute_branch(
+    body: &[AstNode],
+    state: &mut InterpreterState,
+    ctx: &mut ExecContext,
+    statement: &str,
+    span: Span,
+) -> Result<(), ChiruError> {
+    enter_scope(state, ctx, ScopeKind::Block, None);
+    execute(body, state, ctx);
+    exit_scope(state, ctx, statement, span)
+}
+
+/// Advance to `phase`. In a function body this is what each call will do;
+/// a body advances from the phase just before its first advance.
+fn advance_to(
+    phase: &str,
+    state: &mut InterpreterState,
+    ctx: &mut ExecContext,
+) -> Result<(), ChiruError> {
+    if let Some(scope) = ctx.unadvanced_function() {
+        state.rebase_phase(scope, phase);
+    }
+    state.advance_phase(phase)?;
+    ctx.note_advance(phase);
+    Ok(())
+}
+
+/// Declare every function of a statement list before any statement runs,
+/// so that calls may name functions declared further down.
+fn declare_functions(nodes: &[AstNode], state: &mut InterpreterState, ctx: &mut ExecContext) {
+    let scope_id = state.current_scope();
+    for node in nodes {
+        let NodeKind::Function { name, .. } = &node.kind else {
+            continue;
+        };
+        match ctx.ensure_undeclared(scope_id, Namespace::Function, name) {
+            Ok(()) => ctx.declare_function(scope_id, node),
+            Err(e) => state.record_violation(e, node.kind.to_string(), node.span),
+        }
+    }
+}
+
+/// Verify the body of function `function_id` against its signature and
+/// complete its summary.
+fn verify_function(
+    node: &AstNode,
+    function_id: u64,
+    state: &mut InterpreterState,
+    ctx: &mut ExecContext,
+) -> Result<(), ChiruError> {
+    let NodeKind::Function { name, params, body } = &node.kind else {
+        unreachable!("Only function declarations are verified");
+    };
+
+    // A parameter that cannot be declared is reported and left out
+    let mut signature: Vec<(String, CapabilityKind)> = Vec::new();
+    for param in params {
+        let kind = if signature.iter().any(|(p, _)| p == &param.name) {
+            Err(ChiruError::DuplicateDeclaration {
+                kind: "parameter".to_string(),
+                name: param.name.clone(),
+            })
+        } else {
+            capability_kind(&param.capability)
+        };
+        match kind {
+            Ok(kind) => signature.push((param.name.clone(), kind)),
+            Err(e) => state.record_violation(e, node.kind.to_string(), node.span),
+        }
+    }
+
+    // The phases the body goes through are only entered by its calls
+    let saved = state.save_phase();
+    let id = enter_scope(state, ctx, ScopeKind::Function, Some(name.clone()));
+    let values = state.bind_parameters(name, &signature);
+    for ((param, _), value_id) in signature.iter().zip(&values) {
+        ctx.declare(id, Namespace::Value, param, *value_id);
+    }
+    let borrowed: Vec<ValueId> = signature.iter()
+        .zip(&values)
+        .filter(|((_, kind), _)| *kind != CapabilityKind::Own)
+        .map(|(_, value)| *value)
+        .collect();
+    ctx.begin_summary(function_id, id, name, signature, &values, state.violations.len());
+
+    execute(body, state, ctx);
+    let exited = exit_scope(state, ctx, &node.kind.to_string(), node.span);
+
+    // What the body moved out of the function is only put in place by its
+    // calls; Own parameters moved out go the way of their arguments
+    for value in state.escaped_values(id) {
+        if borrowed.contains(&value) {
+            continue;
+        }
+        let name = state.graph.values[&value].name.clone();
+        let target = state.owner(value).expect("Escaped value has an owner");
+        ctx.unbind(target, &name, value);
+        let assumptions = state.retract_value(value);
+        if !values.contains(&value) {
+            ctx.note_move(Escape { name, value, target, assumptions });
+        }
+    }
+
+    state.restore_phase(saved);
+    ctx.finish_summary(state.violations.len());
+    exited
+}
+
+/// Verify a function called before its declaration was reached, in the
+/// scope that declares it. It sees what that scope had declared by the
+/// time of the call.
+fn verify_ahead(
+    node: &AstNode,
+    function_id: u64,
+    declared_in: ScopeId,
+    state: &mut InterpreterState,
+    ctx: &mut ExecContext,
+) {
+    let depth = state.scope_stack.iter()
+        .position(|s| *s == declared_in)
+        .expect("Called function declared outside the scope chain")
+        + 1;
+    let scopes = state.scope_stack.split_off(depth);
+    let frames = ctx.hide_frames(depth);
+
+    if let Err(e) = verify_function(node, function_id, state, ctx) {
+        state.record_violation(e, node.kind.to_string(), node.span);
+    }
+
+    state.scope_stack.extend(scopes);
+    ctx.restore_frames(frames);
+}
+
+/// Declare the region of a `region` statement.
+fn execute_region(
+    kind: &str,
+    name: &str,
+    label: Option<&str>,
+    assumption: Option<&String>,
+    span: Span,
+    state: &mut InterpreterState,
+    ctx: &mut ExecContext,
+) -> Result<(), ChiruError> {
+    let k = match kind {
+        "heap" => RegionKind::Heap,
+        "stack" => RegionKind::Stack,
+        "external" => RegionKind::External,
+        "static" => RegionKind::Static,
+        _ => return Err(ChiruError::UnknownRegionKind { kind: kind.to_string() }),
+    };
+
+    let label = match label {
+        None | Some("public") => Label::Public,
+        Some("secret") => Label::Secret,
+        Some(other) => return Err(ChiruError::UnknownLabel { label: other.to_string() }),
+    };
+
+    // Memory from outside the program is only trusted under unsafe or an
+    // explicit assumption
+    let external = matches!(k, RegionKind::External);
+    if external && assumption.is_none() && !state.is_in_unsafe_scope() {
+        return Err(ChiruError::ExternalRegionOutsideUnsafe { region: name.to_string() });
+    }
+
+    let scope_id = state.current_scope();
+    ctx.ensure_undeclared(scope_id, Namespace::Region, name)?;
+
+    let id = state.declare_region(name.to_string(), k, label)?;
+    ctx.declare(scope_id, Namespace::Region, name, id);
+
+    if let Some(text) = assumption {
+        let phase_id = state.current_phase();
+        let assumption_id = state.add_region_assumption(text.clone(), phase_id, id, span);
+        ctx.note_assumption(assumption_id);
+    }
+    Ok(())
+}
+
+/// Allocate the value of a `let`, derived from `sources` if any.
+fn execute_let(
+    name: &str,
+    region: &str,
+    sources: &[String],
+    secret: bool,
+    span: Span,
+    state: &mut InterpreterState,
+    ctx: &mut ExecContext,
+) -> Result<(), ChiruError> {
+    let region_id = ctx.lookup(Namespace::Region, region)?;
+    let scope_id = state.current_scope();
+    ctx.ensure_undeclared(scope_id, Namespace::Value, name)?;
+
+    let label = state.value_label(region_id, secret);
+    let mut source_ids = Vec::new();
+    let mut declassified = Vec::new();
+    for source in sources {
+        let source_id = ctx.lookup(Namespace::Value, source)?;
+
+        state.check_thread_access(source_id, false)?;
+        state.access_value(source_id, Access::Read)?;
+        if let Some(assumptions) = state.check_flow(source_id, name, label)? {
+            declassified.push((source_id, assumptions));
+        }
+        source_ids.push(source_id);
+    }
+
+    let value_id = state.allocate_value(name.to_string(), region_id, source_ids, secret)?;
+    ctx.declare(scope_id, Namespace::Value, name, value_id);
+
+    for (source_id, assumptions) in declassified {
+        state.record_declassification(source_id, value_id, assumptions, span);
+    }
+    Ok(())
+}
+
+/// Execute a whole program inside the module scope.
+///
+/// Phase declarations must come before any other module-level statement.
+/// A file that declares no phase runs in a single implicit module phase.
+/// Violations are recorded in `state`; execution never stops early.
+pub fn execute_program(
+    nodes: &[AstNode],
+    state: &mut InterpreterState,
+    ctx: &mut ExecContext,
+) {
+    let mut seen_statement = false;
+    let mut body = Vec::new();
+
+    for node in nodes {
+        match &node.kind {
+            NodeKind::PhaseDecl { name } if seen_statement => {
+                state.record_violation(
+                    ChiruError::PhaseDeclaredLate { phase: name.clone() },
+                    node.kind.to_string(),
+                    node.span,
+                );
+            }
+            NodeKind::PhaseDecl { .. } => body.push(node.clone()),
+            _ => {
+                seen_statement = true;
+                body.push(node.clone());
+            }
+        }
+    }
+
+    if state.phases.is_empty()
+        && !body.iter().any(|n| matches!(n.kind, NodeKind::PhaseDecl { .. }))
+    {
+        state.declare_default_phase();
+    }
+
+    enter_scope(state, ctx, ScopeKind::Module, Some("module".to_string()));
+    execute(&body, state, ctx);
+    if let Err(e) = exit_scope(state, ctx, "module", Span::default()) {
+        state.record_violation(e, "module".to_string(), Default::default());
+    }
+}
+
+/// Execute a statement list, recording a violation for each failing
+/// statement and carrying on with the next one.
+pub fn execute(
+    nodes: &[AstNode],
+    state: &mut InterpreterState,
+    ctx: &mut ExecContext,
+) {
+    declare_functions(nodes, state, ctx);
+    for node in nodes {
+        match execute_node(node, state, ctx) {
+            Err(e) if ctx.is_poisoned(&e) => {}
+            Err(e) => state.record_violation(e, node.kind.to_string(), node.span),
+            Ok(()) => {}
+        }
+    }
+}
+
+fn execute_node(
+    node: &AstNode,
+    state: &mut InterpreterState,
+    ctx: &mut ExecContext,
+) -> Result<(), ChiruError> {
+    match &node.kind {
+        // --------------------------------------------------
+        // PHASE DECLARATION (module-level only)
+        // --------------------------------------------------
+        NodeKind::PhaseDecl { name } => {
+            if !state.is_module_scope() {
+                return Err(ChiruError::PhaseNotAtModuleLevel { phase: name.clone() });
+            }
+            state.declare_phase(name.clone())?;
+        }
+
+        // --------------------------------------------------
+        // PHASE TRANSITION (forward, one step at a time)
+        // --------------------------------------------------
+        NodeKind::Advance { phase } => {
+            advance_to(phase, state, ctx)?;
+        }
+
+        // --------------------------------------------------
+        // FUNCTION
+        // --------------------------------------------------
+        // The body is verified once, here, against the signature; each
+        // call applies the resulting summary instead
+        NodeKind::Function { name, .. } => {
+            // Declared when its statement list was entered; a call made
+            // before this point may have verified it already
+            let function_id = ctx.lookup(Namespace::Function, name)?;
+            if ctx.take_pending(function_id, Some(node.span)).is_some() {
+                verify_function(node, function_id, state, ctx)?;
+            }
+        }
+
+        // --------------------------------------------------
+        // IF / ELSE (each path verified on its own copy, then joined)
+        // --------------------------------------------------
+        NodeKind::If { condition: _, then_body, else_body } => {
+            let statement = node.kind.to_string();
+
+            let mut then_state = state.clone();
+            let mut then_ctx = ctx.clone();
+            execute_branch(then_body, &mut then_state, &mut then_ctx, &statement, node.span)?;
+
+            let mut else_state = state.clone();
+            let mut else_ctx = ctx.clone();
+            else_state.continue_ids_from(&then_state);
+            else_ctx.continue_ids_from(&then_ctx);
+            execute_branch(else_body, &mut else_state, &mut else_ctx, &statement, node.span)?;
+
+            let joined = state.join(then_state, else_state);
+            for error in &joined.errors {
+                state.record_violation(error.clone(), statement.clone(), node.span);
+            }
+            ctx.join(then_ctx, else_ctx, &joined);
+        }
+
+        // --------------------------------------------------
+        // LOOP (iterated until the loop state reaches a fixed point)
+        // --------------------------------------------------
+        // The body is deterministic, so once an iteration leaves behind a
+        // state an earlier one did, every later iteration repeats one
+        // already verified. Short of piling up values there are finitely
+        // many such states, so the iterations stop
+        NodeKind::Loop { count, body } => {
+            let statement = node.kind.to_string();
+            let before: HashSet<u64> = state.graph.values.keys().copied().collect();
+            let first_violation = state.violations.len();
+            let first_assumptions: HashSet<AssumptionId> =
+                state.graph.unsafe_assumptions.keys().copied().collect();
+            let mut previous: Option<Vec<String>> = None;
+            let mut reached: Vec<Vec<String>> = Vec::new();
+
+            for iteration in 1..=count.unwrap_or(usize::MAX) {
+                let seen = state.violations.len();
+                let stated: HashSet<AssumptionId> =
+                    state.graph.unsafe_assumptions.keys().copied().collect();
+                execute_branch(body, state, ctx, &statement, node.span)?;
+                forget_repeated_violations(state, first_violation, seen);
+                forget_repeated_assumptions(state, ctx, &first_assumptions, &stated);
+
+                let residue = state.loop_residue(&before);
+                let values = previous.as_ref().map_or_else(Vec::new, |p| added(&residue, p));
+                if !values.is_empty() {
+                    state.record_violation(
+                        ChiruError::LoopDivergence { iteration, values },
+                        statement.clone(),
+                        node.span,
+                    );
+                    break;
+                }
+
+                let loop_state = state.loop_state(&before);
+                if reached.contains(&loop_state) {
+                    break;
+                }
+                reached.push(loop_state);
+                previous = Some(residue);
+            }
+        }
+
+        // --------------------------------------------------
+        // PLAIN BLOCK (a shorter scope, no unsafe taint)
+        // --------------------------------------------------
+        NodeKind::Block { name, body } => {
+            enter_scope(state, ctx, ScopeKind::Block, name.clone());
+            execute(body, state, ctx);
+            exit_scope(state, ctx, &node.kind.to_string(), node.span)?;
+        }
+
+        // --------------------------------------------------
+        // UNSAFE BLOCK
+        // --------------------------------------------------
+        NodeKind::Unsafe { body } => {
+            enter_scope(state, ctx, ScopeKind::Unsafe, None);
+            execute(body, state, ctx);
+            exit_scope(state, ctx, &node.kind.to_string(), node.span)?;
+        }
+
+        // --------------------------------------------------
+        // DECLASSIFY BLOCK (high-to-low flows, unsafe only)
+        // --------------------------------------------------
+        NodeKind::Declassify { body } => {
+            if !state.is_in_unsafe_scope() {
+                return Err(ChiruError::DeclassifyOutsideUnsafe);
+            }
+
+            enter_scope(state, ctx, ScopeKind::Declassify, None);
+            execute(body, state, ctx);
+            exit_scope(state, ctx, &node.kind.to_string(), node.span)?;
+        }
+
+        // --------------------------------------------------
+        // SPAWN BLOCK (concurrent context)
+        // --------------------------------------------------
+        NodeKind::Spawn { body } => {
+            let id = enter_scope(state, ctx, ScopeKind::Spawn, None);
+            state.begin_spawn(id);
+            execute(body, state, ctx);
+            exit_scope(state, ctx, &node.kind.to_string(), node.span)?;
+        }
+
+        // --------------------------------------------------
+        // REGION DECLARATION
+        // --------------------------------------------------
+        NodeKind::Region { kind, name, label, assumption } => {
+            let scope_id = state.current_scope();
+            let result = execute_region(
+                kind,
+                name,
+                label.as_deref(),
+                assumption.as_ref(),
+                node.span,
+                state,
+                ctx,
+            );
+            if result.is_err() {
+                ctx.poison(scope_id, Namespace::Region, name);
+            }
+            result?;
+        }
+
+        // --------------------------------------------------
+        // LIFETIME DECLARATION (bound to an enclosing scope,
+        // phase-bound implicitly)
+        // --------------------------------------------------
+        NodeKind::Lifetime { name, scope } => {
+            let result = state.resolve_scope(name, scope).and_then(|scope_id| {
+                ctx.ensure_undeclared(scope_id, Namespace::Lifetime, name)?;
+                let phase_id = state.current_phase();
+
+                let id = state.create_lifetime(name.clone(), scope_id, phase_id)?;
+                ctx.declare(scope_id, Namespace::Lifetime, name, id);
+                Ok(())
+            });
+            if result.is_err() {
+                let scope_id = state.resolve_scope(name, scope)
+                    .unwrap_or_else(|_| state.current_scope());
+                ctx.poison(scope_id, Namespace::Lifetime, name);
+            }
+            result?;
+        }
+
+        // --------------------------------------------------
+        // VALUE ALLOCATION
+        // --------------------------------------------------
+        // A derived value reads each value it is derived from, and each of
+        // them must be allowed to flow into it
+        NodeKind::Let { name, region, sources, secret } => {
+            let scope_id = state.current_scope();
+            let result = execute_let(name, region, sources, *secret, node.span, state, ctx);
+            if result.is_err() {
+                ctx.poison(scope_id, Namespace::Value, name);
+            }
+            result?;
+        }
+
+        // --------------------------------------------------
+        // CAPABILITY GRANT (phase-enforced)
+        // --------------------------------------------------
+        NodeKind::Capability {
+            kind,
+            value,
+            lifetime,
+            name,
+            parent,
+        } => {
+            let cap_kind = capability_kind(kind)?;
+
+            let value_id = ctx.lookup(Namespace::Value, value)?;
+            let lifetime_id = ctx.lookup(Namespace::Lifetime, lifetime)?;
+            let parent_id = match parent {
+                Some(parent) => Some(resolve_capability(parent, state, ctx)?),
+                None => None,
+            };
+
+            let scope_id = state.current_scope();
+            if let Some(name) = name {
+                ctx.ensure_undeclared(scope_id, Namespace::Capability, name)?;
+            }
+
+            let phase_id = state.current_phase();
+
+            state.check_thread_access(value_id, cap_kind.is_exclusive())?;
+            let id = state.create_capability(
+                cap_kind,
+                value_id,
+                lifetime_id,
+                phase_id,
+                parent_id,
+            )?;
+
+            if let Some(name) = name {
+                ctx.declare(scope_id, Namespace::Capability, name, id);
+            }
+        }
+
+        // --------------------------------------------------
+        // CAPABILITY RELEASE (ends a grant before its lifetime)
+        // --------------------------------------------------
+        NodeKind::Release { target } => {
+            let cap_id = resolve_capability(target, state, ctx)?;
+            state.release_capability(cap_id)?;
+        }
+
+        // --------------------------------------------------
+        // CAPABILITY DOWNGRADE (down the capability lattice)
+        // --------------------------------------------------
+        NodeKind::Downgrade { target, kind } => {
+            let cap_kind = capability_kind(kind)?;
+            let cap_id = resolve_capability(target, state, ctx)?;
+            state.downgrade_capability(cap_id, cap_kind)?;
+        }
+
+        // --------------------------------------------------
+        // DROP VALUE (capability + phase checked internally)
+        // --------------------------------------------------
+        NodeKind::Drop { value } => {
+            let value_id = ctx.lookup(Namespace::Value, value)?;
+
+            state.check_thread_access(value_id, true)?;
+            state.drop_value(value_id)?;
+
+            let zeroized = state.graph.values[&value_id].zeroized;
+            ctx.note_discharge(value_id, Discharge::Dropped { zeroized });
+        }
+
+        // --------------------------------------------------
+        // COPY (information flow checked against labels)
+        // --------------------------------------------------
+        NodeKind::Copy { from, into } => {
+            let from_id = ctx.lookup(Namespace::Value, from)?;
+            let into_id = ctx.lookup(Namespace::Value, into)?;
+
+            state.check_thread_access(from_id, false)?;
+            state.check_thread_access(into_id, true)?;
+
+            let label = state.graph.values[&into_id].label;
+            let declassified = state.check_flow(from_id, into, label)?;
+            state.copy_value(from_id, into_id)?;
+            ctx.note_write(into_id);
+            if !state.is_in_declassify_scope() {
+                ctx.note_flow(&state.lineage(from_id), into_id);
+            }
+
+            if let Some(assumptions) = declassified {
+                state.record_declassification(from_id, into_id, assumptions, node.span);
+            }
+        }
+
+        // --------------------------------------------------
+        // ZEROIZE (wipes the value; a write)
+        // --------------------------------------------------
+        NodeKind::Zeroize { value } => {
+            let value_id = ctx.lookup(Namespace::Value, value)?;
+
+            state.check_thread_access(value_id, true)?;
+            state.zeroize_value(value_id)?;
+        }
+
+        // --------------------------------------------------
+        // RETURN (external memory handed back to its region)
+        // --------------------------------------------------
+        NodeKind::Return { value, region } => {
+            let value_id = ctx.lookup(Namespace::Value, value)?;
+            let region_id = ctx.lookup(Namespace::Region, region)?;
+
+            state.check_thread_access(value_id, true)?;
+            state.return_value(value_id, region_id)?;
+        }
+
+        // --------------------------------------------------
+        // MOVE (ownership transfer to an enclosing scope)
+        // --------------------------------------------------
+        NodeKind::Move { value, target } => {
+            let value_id = ctx.lookup(Namespace::Value, value)?;
+            let target_id = state.find_enclosing_scope(target).ok_or_else(|| {
+                ChiruError::MoveTargetNotEnclosing {
+                    value: value.clone(),
+                    scope: target.clone(),
+                }
+            })?;
+
+            // A binding of this very value in the target means the value is
+            // not ours to move; let `move_value` report that instead.
+            let current = state.current_scope();
+            if target_id != current
+                && !ctx.is_bound(target_id, Namespace::Value, value, value_id)
+            {
+                ctx.ensure_undeclared(target_id, Namespace::Value, value)?;
+            }
+
+            state.move_value(value_id, target_id)?;
+
+            if target_id != current {
+                ctx.move_binding(current, target_id, value, value_id);
+            }
+            // A move out of the function is part of its summary
+            let escapes = ctx.function_scope()
+                .is_some_and(|scope| !state.is_within(target_id, scope));
+            if escapes {
+                ctx.note_discharge(value_id, Discharge::Moved(target_id));
+            }
+        }
+
+        // --------------------------------------------------
+        // VALUE ACCESS (checked against live capabilities)
+        // --------------------------------------------------
+        NodeKind::Read { value } => {
+            let value_id = ctx.lookup(Namespace::Value, value)?;
+
+            state.check_thread_access(value_id, false)?;
+            state.access_value(value_id, Access::Read)?;
+        }
+
+        NodeKind::Write { value } => {
+            let value_id = ctx.lookup(Namespace::Value, value)?;
+
+            state.check_thread_access(value_id, true)?;
+            state.write_value(value_id)?;
+            ctx.note_write(value_id);
+        }
+
+        // An opaque call reads each value it is given
+        NodeKind::Call { function: _, values } => {
+            for value in values {
+                let value_id = ctx.lookup(Namespace::Value, value)?;
+
+                state.check_thread_access(value_id, false)?;
+                state.access_value(value_id, Access::Read)?;
+            }
+        }
+
+        // --------------------------------------------------
+        // FUNCTION CALL (the callee's summary applied, never its body)
+        // --------------------------------------------------
+        NodeKind::CallFunction { function, args } => {
+            let function_id = ctx.lookup(Namespace::Function, function)?;
+            if let Some((declared_in, declaration)) = ctx.take_pending(function_id, None) {
+                verify_ahead(&declaration, function_id, declared_in, state, ctx);
+            }
+            let summary = ctx.summary(function_id)?.clone();
+            ctx.note_call(&summary);
+
+            let params: Vec<CapabilityKind> = summary.params.iter().map(|(_, k)| *k).collect();
+            if args.len() != params.len() {
+                return Err(ChiruError::ArityMismatch {
+                    function: function.clone(),
+                    expected: params.len(),
+                    found: args.len(),
+                });
+            }
+
+            let mut values = Vec::new();
+            for (arg, kind) in args.iter().zip(&params) {
+                let value_id = ctx.lookup(Namespace::Value, arg)?;
+
+                state.check_thread_access(value_id, kind.is_exclusive())?;
+                state.check_call_argument(function, value_id, *kind)?;
+                values.push(value_id);
+            }
+
+            // An argument passed to an exclusive parameter cannot be passed
+            // to any other parameter of the same call
+            for (i, value_id) in values.iter().enumerate() {
+                let aliased = values.iter().enumerate().any(|(j, other)| {
+                    i != j && value_id == other
+                        && (params[i].is_exclusive() || params[j].is_exclusive())
+                });
+                if aliased {
+                    return Err(ChiruError::AliasedArgument {
+                        function: function.clone(),
+                        value: args[i].clone(),
+                    });
+                }
+            }
+
+            // What flows between parameters in the body flows between the
+            // arguments, which may carry labels the parameters did not
+            let position = |name: &String| {
+                summary.params.iter()
+                    .position(|(param, _)| param == name)
+                    .expect("Flow between parameters of the callee")
+            };
+            let mut flows = Vec::new();
+            for (from, into) in &summary.flows {
+                let (from, into) = (position(from), position(into));
+                let label = state.graph.values[&values[into]].label;
+                let declassified = state.check_flow(values[from], &args[into], label)?;
+                flows.push((values[from], values[into], declassified));
+            }
+            for (from_id, into_id, declassified) in flows {
+                state.flow_into(from_id, into_id);
+                if !state.is_in_declassify_scope() {
+                    ctx.note_flow(&state.lineage(from_id), into_id);
+                }
+                if let Some(assumptions) = declassified {
+                    state.record_declassification(from_id, into_id, assumptions, node.span);
+                }
+            }
+
+            // Each call leaves a new value behind for each one the body
+            // moved out of the function
+            for escape in &summary.moves {
+                let target = state.counterpart(escape.target);
+                let value_id = state.instantiate_value(escape.value, target, &escape.assumptions);
+                ctx.declare(target, Namespace::Value, &escape.name, value_id);
+            }
+
+            // What the body writes through a parameter, the call writes
+            for ((param, _), value_id) in summary.params.iter().zip(&values) {
+                if summary.writes.contains(param) {
+                    state.clobber_value(*value_id);
+                    ctx.note_write(*value_id);
+                }
+            }
+
+            // Own parameters take their arguments with them, and each
+            // argument is discharged the way the body discharged the parameter
+            let statement = node.kind.to_string();
+            for (((param, kind), arg), value_id) in summary.params.iter().zip(args).zip(values) {
+                if *kind != CapabilityKind::Own {
+                    continue;
+                }
+                state.consume_argument(value_id)?;
+                ctx.consume_binding(arg);
+
+                let discharge = summary.consumes.iter()
+                    .find(|(name, _)| name == param)
+                    .map_or(Discharge::Kept, |(_, discharge)| match *discharge {
+                        Discharge::Moved(target) => Discharge::Moved(state.counterpart(target)),
+                        discharge => discharge,
+                    });
+                let error = match discharge {
+                    Discharge::Kept => state.abandon_value(value_id),
+                    Discharge::Dropped { zeroized } => {
+                        state.drop_argument(value_id, zeroized).err()
+                    }
+                    Discharge::Moved(target) => {
+                        state.hand_over(value_id, target);
+                        ctx.declare(target, Namespace::Value, arg, value_id);
+                        None
+                    }
+                };
+                if let Some(error) = error {
+                    state.record_violation(error, statement.clone(), node.span);
+                }
+
+                // Passing on an Own parameter discharges it the same way,
+                // unless it only moves within the function
+                let escapes = match discharge {
+                    Discharge::Moved(target) => ctx.function_scope()
+                        .is_none_or(|scope| !state.is_within(target, scope)),
+                    _ => true,
+                };
+                if escapes {
+                    ctx.note_discharge(value_id, discharge);
+                }
+            }
+
+            // The call goes through the phases the body went through
+            for phase in &summary.advances {
+                advance_to(phase, state, ctx)?;
+            }
+        }
+
+        // --------------------------------------------------
+        // UNSAFE ASSUMPTION (phase-bound)
+        // --------------------------------------------------
+        NodeKind::Assume { text, affects } => {
+            let affected = match affects {
+                Some(names) => Some(
+                    names.iter()
+                        .map(|name| ctx.lookup(Namespace::Value, name))
+                        .collect::<Result<Vec<_>, _>>()?,
+                ),
+                None => None,
+            };
+            let phase_id = state.current_phase();
+
+            let id = state.add_unsafe_assumption(
+                text.clone(),
+                phase_id,
+                affected,
+                node.span,
+            )?;
+            ctx.note_assumption(id);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn run(source: &str) -> (InterpreterState, ExecContext) {
+        let ast = parse("test.chiru", source).unwrap_or_else(|e| panic!("{}", e));
+        let mut state = InterpreterState::new();
+        let mut ctx = ExecContext::new();
+        execute_program(&ast, &mut state, &mut ctx);
+        (state, ctx)
+    }
+
+    fn codes(source: &str) -> Vec<&'static str> {
+        run(source).0.violations.iter().map(|v| v.code).collect()
+    }
+
+    #[test]
+    fn headline_examples_verify() {
+        assert!(codes(include_str!("../examples/payment.chiru")).is_empty());
+        assert!(codes(include_str!("../examples/payment_hsm.chiru")).is_empty());
+    }
+
+    #[test]
+    fn duplicate_and_late_phases_are_reported() {
+        let source = "
+            phase init
+            phase init
+            function f {
+            }
+            phase late
+        ";
+        assert_eq!(codes(source), ["CH0009", "CH0007"]);
+    }
+
+    #[test]
+    fn external_and_static_memory_rules() {
+        let source = r#"
+            function f {
+              region external io assume "the device keeps io mapped"
+              region static table
+              region heap h
+              let a in io
+              let b in io
+              let t in table
+              let x in h
+              return a to h
+              return x to h
+              drop x
+              drop t
+              return a to io
+              region external raw
+            }
+        "#;
+        assert_eq!(codes(source), ["CH0049", "CH0050", "CH0051", "CH0052", "CH0048"]);
+    }
+
+    #[test]
+    fn phase_inside_function_is_rejected() {
+        let source = "
+            function f {
+              phase inner
+            }
+        ";
+        assert_eq!(codes(source), ["CH0008"]);
+    }
+
+    #[test]
+    fn advance_goes_one_phase_forward() {
+        let source = "
+            phase init
+            phase operate
+            phase teardown
+
+            advance to operate
+            advance to teardown
+        ";
+        let (state, _) = run(source);
+        assert!(state.violations.is_empty());
+        assert_eq!(state.current_phase_name(), "teardown");
+    }
+
+    #[test]
+    fn advance_to_unknown_phase_is_reported() {
+        let source = "
+            phase init
+            advance to operate
+        ";
+        assert_eq!(codes(source), ["CH0006"]);
+    }
+
+    #[test]
+    fn advance_cannot_go_back_or_skip() {
+        let source = "
+            phase init
+            phase operate
+            phase teardown
+
+            advance to teardown
+            advance to operate
+            advance to init
+        ";
+        assert_eq!(codes(source), ["CH0011", "CH0010"]);
+    }
+
+    #[test]
+    fn advance_expires_lifetimes_of_the_phase_left() {
+        let source = "
+            phase init
+            phase operate
+
+            function f {
+              lifetime setup bound to f
+              region stack s
+              let v in s
+              capability SharedRead v during setup
+              read v
+              advance to operate
+              read v
+            }
+        ";
+        assert_eq!(codes(source), ["CH0029"]);
+    }
+
+    #[test]
+    fn dropping_twice_is_reported() {
+        let source = "
+            function f {
+              region heap h
+              let a in h
+              drop a
+              drop a
+            }
+        ";
+        assert_eq!(codes(source), ["CH0017"]);
+    }
+
+    #[test]
+    fn failed_let_does_not_cascade() {
+        let source = "
+            function f {
+              region heap h
+              let d in missing
+              let e from d in h
+              read e
+              drop e
+            }
+        ";
+        assert_eq!(codes(source), ["CH0002"]);
+    }
+
+    #[test]
+    fn failed_lifetime_and_region_do_not_cascade() {
+        let source = "
+            function f {
+              lifetime l bound to nowhere
+              region weird r
+              let v in r
+              region stack s
+              let x in s
+              capability SharedRead x during l
+            }
+        ";
+        assert_eq!(codes(source), ["CH0021", "CH0004"]);
+    }
+
+    #[test]
+    fn lifetime_lives_in_the_scope_it_is_bound_to() {
+        let source = "
+            function f {
+              region stack s
+              let v in s
+              block setup {
+                lifetime outer bound to f
+              }
+              lifetime stale bound to setup
+              capability SharedRead v during outer
+              read v
+            }
+        ";
+        assert_eq!(codes(source), ["CH0022"]);
+    }
+
+    #[test]
+    fn names_resolve_through_the_enclosing_scopes_only() {
+        let source = "
+            function f {
+              region stack s
+              region heap s
+              block inner {
+                region stack r
+                let s in r
+              }
+              let x in r
+              let y in s
+            }
+
+            function g {
+              let z in s
+            }
+        ";
+        assert_eq!(codes(source), ["CH0023", "CH0024", "CH0024"]);
+    }
+
+    #[test]
+    fn assumptions_affect_named_or_enclosed_values_only() {
+        let source = r#"
+            function f {
+              region stack s
+              let before in s
+              unsafe {
+                assume "before is initialized" affects before
+                assume "the block's values are initialized"
+                let inside in s
+              }
+            }
+
+            function g {
+              region stack t
+              let other in t
+            }
+        "#;
+        let (state, _) = run(source);
+        assert!(state.violations.is_empty());
+
+        let affected = |text: &str| {
+            let assumption = state.graph.unsafe_assumptions.values()
+                .find(|ua| ua.description == text)
+                .unwrap();
+            let names: Vec<_> = assumption.affected_values.iter()
+                .map(|v| state.graph.values[v].name.as_str())
+                .collect();
+            (names, assumption.inferred)
+        };
+        assert_eq!(affected("before is initialized"), (vec!["before"], false));
+        assert_eq!(affected("the block's values are initialized"), (vec!["inside"], true));
+    }
+
+    #[test]
+    fn write_racing_with_a_concurrent_read_is_reported() {
+        let source = "
+            function workers {
+              lifetime run bound to workers
+              region stack pool
+              let ledger in pool
+              capability ThreadSend ledger during run
+              spawn {
+                capability UniqueMut ledger during run
+                write ledger
+              }
+              spawn {
+                read ledger
+              }
+            }
+        ";
+        assert_eq!(codes(source), ["CH0026"]);
+    }
+
+    #[test]
+    fn shared_reads_and_spawns_in_sequence_do_not_race() {
+        let source = "
+            function workers {
+              lifetime run bound to workers
+              region stack pool
+              let ledger in pool
+              let journal in pool
+              capability ThreadShare ledger during run
+              spawn {
+                read ledger
+              }
+              spawn {
+                read ledger
+              }
+              capability ThreadSend journal during run
+              block first {
+                spawn {
+                  capability UniqueMut journal during run
+                  write journal
+                }
+              }
+              block second {
+                spawn {
+                  capability UniqueMut journal during run
+                  write journal
+                }
+              }
+            }
+        ";
+        assert!(codes(source).is_empty());
+    }
+
+    #[test]
+    fn drop_not_owned_names_the_scope() {
+        let source = "
+            function f {
+              region heap h
+              let v in h
+              unsafe {
+                drop v
+              }
+              drop v
+            }
+        ";
+        let (state, _) = run(source);
+        let messages: Vec<_> = state.violations.iter().map(|v| v.message.as_str()).collect();
+        assert_eq!(messages, ["Cannot drop value `v`: it is not owned by an unsafe block"]);
+    }
+
+    #[test]
+    fn secret_flowing_into_public_is_reported() {
+        let source = "
+            function f {
+              lifetime l bound to f
+              region stack keys label secret
+              region stack logs
+              let key in keys
+              let log in logs
+              capability SharedRead key during l
+              capability UniqueMut log during l
+              copy key into log
+              let kcv from key in logs
+            }
+        ";
+        assert_eq!(codes(source), ["CH0054", "CH0054"]);
+    }
+
+    #[test]
+    fn declassify_needs_unsafe_and_an_assumption() {
+        let source = "
+            function f {
+              lifetime l bound to f
+              region stack keys label secret
+              region stack logs
+              let key in keys
+              capability SharedRead key during l
+              declassify {
+              }
+              unsafe {
+                declassify {
+                  let kcv from key in logs
+                }
+              }
+            }
+        ";
+        assert_eq!(codes(source), ["CH0055", "CH0056"]);
+    }
+
+    #[test]
+    fn declassified_flows_are_recorded() {
+        let source = "
+            function leak(a: SharedRead, b: UniqueMut) {
+              copy a into b
+            }
+
+            function f {
+              lifetime l bound to f
+              region stack keys label secret
+              region stack logs
+              let key in keys
+              let log in logs
+              capability SharedRead key during l
+              capability UniqueMut log during l
+              unsafe {
+                assume \"A key check value reveals nothing about the key\"
+                declassify {
+                  let kcv from key in logs
+                  call leak(key, log)
+                }
+              }
+            }
+        ";
+        let (state, _) = run(source);
+        assert!(state.violations.is_empty());
+        let targets: Vec<_> = state.declassifications.iter()
+            .map(|d| state.graph.values[&d.to].name.as_str())
+            .collect();
+        assert_eq!(targets, ["kcv", "log"]);
+    }
+
+    #[test]
+    fn call_checks_required_capability() {
+        let source = "
+            function g(x: UniqueMut) {
+              write x
+            }
+            function m {
+              region heap h
+              let v in h
+              call g(v)
+              drop v
+            }
+        ";
+        assert_eq!(codes(source), ["CH0060"]);
+    }
+
+    #[test]
+    fn function_body_cannot_capture_outer_values() {
+        let captured = "
+            function f {
+              lifetime l bound to f
+              region stack s
+              let buf in s
+              capability UniqueMut buf during l as c
+              function g {
+                write buf
+                release c
+              }
+              release c
+              call g()
+            }
+        ";
+        assert_eq!(codes(captured), ["CH0066", "CH0066"]);
+
+        let passed = "
+            function f {
+              lifetime l bound to f
+              region stack s
+              let buf in s
+              capability UniqueMut buf during l as c
+              function g(data: UniqueMut) {
+                write data
+              }
+              release c
+              call g(buf)
+            }
+        ";
+        assert_eq!(codes(passed), ["CH0060"]);
+    }
+
+    #[test]
+    fn own_argument_goes_the_way_of_its_parameter() {
+        let source = "
+            function take(x: Own) {
+            }
+            function wipe(x: Own) {
+              zeroize x
+              release Own x
+              drop x
+            }
+            function main {
+              lifetime l bound to main
+              region heap h
+              region external hsm assume \"HSM provides key memory\"
+              let k in h
+              capability Own k during l
+              call take(k)
+              let secret s in h
+              capability Own s during l
+              call wipe(s)
+              let e in hsm
+              capability Own e during l
+              call wipe(e)
+            }
+        ";
+        assert_eq!(codes(source), ["CH0043", "CH0047"]);
+    }
+
+    #[test]
+    fn functions_may_be_called_before_their_declaration() {
+        let source = "
+            function main {
+              call later()
+            }
+            function later {
+              region heap h
+              let x in h
+              drop x
+            }
+        ";
+        assert!(codes(source).is_empty());
+    }
+
+    #[test]
+    fn mutual_recursion_is_reported() {
+        let source = "
+            function a {
+              call b()
+            }
+            function b {
+              call a()
+            }
+        ";
+        let (state, _) = run(source);
+        let cycles: Vec<_> = state.violations.iter().map(|v| &v.error).collect();
+        assert!(matches!(
+            cycles[..],
+            [ChiruError::Recursion { cycle }] if cycle == &["a", "b", "a"]
+        ));
+    }
+
+    #[test]
+    fn caller_of_unsafe_function_is_unsafe() {
+        let source = "
+            function bad {
+              region heap h
+              let x in h
+            }
+            function main {
+              call bad()
+            }
+        ";
+        let (_, ctx) = run(source);
+        let main = ctx.summaries().into_iter().find(|f| f.name == "main").unwrap();
+        assert_eq!(main.violations, 0);
+        assert_eq!(main.unsafe_calls, ["bad"]);
+    }
+
+    #[test]
+    fn call_writes_what_the_callee_writes() {
+        let source = "
+            function fill(buf: UniqueMut) {
+              write buf
+            }
+
+            function main {
+              region heap h
+              lifetime l bound to main
+              let secret k in h
+              capability UniqueMut k during l as w
+              zeroize k
+              call fill(k)
+              release w
+              drop k
+            }
+        ";
+        assert_eq!(codes(source), ["CH0053", "CH0043"]);
+    }
+
+    #[test]
+    fn call_checks_flows_between_arguments() {
+        let source = "
+            function leak(a: SharedRead, b: UniqueMut) {
+              copy a into b
+            }
+
+            function main {
+              region heap h
+              region heap out_mem
+              lifetime l bound to main
+              let secret k in h
+              let out in out_mem
+              capability UniqueMut k during l as kw
+              capability UniqueMut out during l as ow
+              call leak(k, out)
+              zeroize k
+              release kw
+              release ow
+              drop k
+              drop out
+            }
+        ";
+        assert_eq!(codes(source), ["CH0054"]);
+    }
+
+    #[test]
+    fn function_bodies_advance_only_through_their_calls() {
+        let source = "
+            phase init
+            phase operate
+
+            function a {
+              advance to operate
+            }
+
+            function b {
+              advance to operate
+            }
+        ";
+        assert!(codes(source).is_empty());
+    }
+
+    #[test]
+    fn each_call_replays_the_phase_transitions_of_its_body() {
+        let source = "
+            phase init
+            phase operate
+
+            function a {
+              advance to operate
+            }
+
+            function main {
+              call a()
+              call a()
+            }
+        ";
+        assert_eq!(codes(source), ["CH0010"]);
+    }
+
+    #[test]
+    fn call_to_unknown_function_is_reported() {
+        let source = "
+            function f {
+              call nope()
+            }
+        ";
+        assert_eq!(codes(source), ["CH0058"]);
+    }
+
+    #[test]
+    fn drop_on_one_path_only_is_inconsistent() {
+        let source = "
+            function f {
+              region heap h
+              let b in h
+              if ok {
+                drop b
+              }
+            }
+        ";
+        assert_eq!(codes(source), ["CH0063"]);
+    }
+
+    #[test]
+    fn value_created_on_one_path_only_is_inconsistent() {
+        let source = "
+            function f {
+              region heap h
+              if ok {
+                let b in h
+                move b to f
+              }
+              drop b
+            }
+        ";
+        assert_eq!(codes(source), ["CH0063", "CH0001"]);
+    }
+
+    #[test]
+    fn values_created_on_both_paths_are_joined() {
+        let source = "
+            function f {
+              region heap h
+              if ok {
+                let b in h
+                move b to f
+              } else {
+                let b in h
+                move b to f
+              }
+              drop b
+            }
+        ";
+        assert!(codes(source).is_empty());
+    }
+
+    #[test]
+    fn value_handed_out_on_one_path_stays_owed() {
+        let source = "
+            function outer {
+              region heap h
+              block inner {
+                let buf in h
+                if ok {
+                  move buf to outer
+                } else {
+                  drop buf
+                }
+              }
+            }
+        ";
+        assert_eq!(codes(source), ["CH0067", "CH0043"]);
+    }
+
+    #[test]
+    fn capability_released_on_one_path_is_reported() {
+        let source = "
+            function f {
+              lifetime l bound to f
+              region heap h
+              let buf in h
+              capability UniqueMut buf during l as w
+              if ok {
+                release w
+              } else {
+              }
+              drop buf
+            }
+        ";
+        assert_eq!(codes(source), ["CH0068"]);
+    }
+
+    #[test]
+    fn allocation_per_iteration_diverges() {
+        let source = "
+            function f {
+              region heap h
+              loop {
+                let c in h
+              }
+            }
+        ";
+        assert_eq!(codes(source), ["CH0043", "CH0065"]);
+    }
+
+    #[test]
+    fn assumption_in_loop_is_stated_once() {
+        let source = "
+            function f {
+              loop {
+                unsafe {
+                  assume \"device ready\"
+                }
+              }
+            }
+        ";
+        let (state, _) = run(source);
+        let stated: Vec<_> = state.graph.unsafe_assumptions.values()
+            .map(|a| a.description.as_str())
+            .collect();
+        assert_eq!(stated, ["device ready"]);
+    }
+
+    #[test]
+    fn block_drops_value_of_enclosing_scope() {
+        let source = "
+            function f {
+              region heap h
+              let v in h
+              block inner {
+                drop v
+              }
+            }
+        ";
+        assert!(codes(source).is_empty());
+    }
+
+    #[test]
+    fn each_call_moves_a_new_value_out() {
+        let once = "
+            function outer {
+              region heap h
+              function make {
+                let v in h
+                move v to outer
+              }
+              call make()
+              drop v
+            }
+        ";
+        assert!(codes(once).is_empty());
+
+        let twice = "
+            function outer {
+              region heap h
+              function make {
+                let v in h
+                move v to outer
+              }
+              call make()
+              call make()
+              drop v
+            }
+        ";
+        assert_eq!(codes(twice), ["CH0043"]);
+    }
+
+    #[test]
+    fn function_declared_in_a_loop_is_verified_once() {
+        let source = "
+            function f {
+              repeat 2 {
+                function g {
+                  drop nothing
+                }
+              }
+            }
+        ";
+        let (state, ctx) = run(source);
+        let codes: Vec<_> = state.violations.iter().map(|v| v.code).collect();
+        assert_eq!(codes, ["CH0001"]);
+        let names: Vec<_> = ctx.summaries().iter().map(|f| f.name.clone()).collect();
+        assert_eq!(names, ["f", "g"]);
+    }
+
+    #[test]
+    fn loop_runs_until_its_state_repeats() {
+        // The second iteration drops `b`, so only the third drops it twice
+        let source = "
+            function f {
+              lifetime l bound to f
+              region heap h
+              let b in h
+              capability SharedRead b during l as r
+              loop {
+                drop b
+                release r
+              }
+            }
+        ";
+        assert_eq!(codes(source), ["CH0019", "CH0037", "CH0017"]);
+    }
+}
@@ -0,0 +1,1644 @@
+/// Long-form documentation for each error code, printed by `chiru explain`.
+pub struct Explanation {
+    pub code: &'static str,
+    pub title: &'static str,
+    pub text: &'static str,
+    pub failing: &'static str,
+    pub fixed: &'static str,
+}
+
+pub fn lookup(code: &str) -> Option<&'static Explanation> {
+    EXPLANATIONS.iter().find(|e| e.code.eq_ignore_ascii_case(code))
+}
+
+pub fn render(e: &Explanation) -> String {
+    let indent = |src: &str| {
+        src.lines()
+            .map(|l| if l.is_empty() { String::new() } else { format!("    {}", l) })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    // Internal-error entries have no program that triggers them
+    let mut out = format!("{}: {}\n\n{}", e.code, e.title, e.text);
+    if !e.failing.is_empty() {
+        out.push_str(&format!("\n\nFailing example:\n\n{}", indent(e.failing)));
+    }
+    if !e.fixed.is_empty() {
+        out.push_str(&format!("\n\nFixed example:\n\n{}", indent(e.fixed)));
+    }
+    out
+}
+
+pub static EXPLANATIONS: &[Explanation] = &[
+    Explanation {
+        code: "CH0001",
+        title: "Unknown value",
+        text: "\
+A statement refers to a value name that was never introduced with `let`.
+Every value must be allocated in a region before it can be given
+capabilities, dropped or referenced by an assumption.",
+        failing: "\
+function f {
+  drop buffer
+}",
+        fixed: "\
+function f {
+  region stack s
+  let buffer in s
+  drop buffer
+}",
+    },
+    Explanation {
+        code: "CH0002",
+        title: "Unknown region",
+        text: "\
+A `let` names a region that was never declared. Chiru requires every
+value to state where its memory comes from, so the region must be
+declared with `region <kind> <name>` first.",
+        failing: "\
+function f {
+  let buffer in scratch
+}",
+        fixed: "\
+function f {
+  region stack scratch
+  let buffer in scratch
+}",
+    },
+    Explanation {
+        code: "CH0003",
+        title: "Unknown lifetime",
+        text: "\
+A capability is granted `during` a lifetime that was never declared.
+Lifetimes are explicit: declare them with `lifetime <name> bound to <scope>`.",
+        failing: "\
+function f {
+  region stack s
+  let v in s
+  capability SharedRead v during call
+}",
+        fixed: "\
+function f {
+  lifetime call bound to f
+  region stack s
+  let v in s
+  capability SharedRead v during call
+}",
+    },
+    Explanation {
+        code: "CH0004",
+        title: "Unknown region kind",
+        text: "\
+Region kinds are a closed set: `stack`, `heap`, `external` and `static`.",
+        failing: "\
+function f {
+  region pool p
+}",
+        fixed: "\
+function f {
+  region heap p
+}",
+    },
+    Explanation {
+        code: "CH0005",
+        title: "Unknown capability kind",
+        text: "\
+Capability kinds are a closed set. The spelling is case-sensitive.",
+        failing: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability Read v during l
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability SharedRead v during l
+}",
+    },
+    Explanation {
+        code: "CH0006",
+        title: "Unknown phase",
+        text: "\
+An `advance to` statement names a phase that was never declared.
+Phases are declared at the top of the file with `phase <name>`.",
+        failing: "\
+phase init
+
+function f {
+  advance to operate
+}",
+        fixed: "\
+phase init
+phase operate
+
+function f {
+  advance to operate
+}",
+    },
+    Explanation {
+        code: "CH0007",
+        title: "Duplicate phase declaration",
+        text: "\
+Each phase name may be declared only once; the declaration order defines
+the order in which phases are entered.",
+        failing: "\
+phase init
+phase init",
+        fixed: "\
+phase init
+phase operate",
+    },
+    Explanation {
+        code: "CH0008",
+        title: "Phase declared outside module level",
+        text: "\
+Phases describe the whole program, so they can only be declared at
+module level, never inside a function or block.",
+        failing: "\
+function f {
+  phase init
+}",
+        fixed: "\
+phase init
+
+function f {
+}",
+    },
+    Explanation {
+        code: "CH0009",
+        title: "Phase declared after other statements",
+        text: "\
+All phase declarations must come before any other module-level statement,
+so that every statement runs in a known phase.",
+        failing: "\
+function f {
+}
+
+phase init",
+        fixed: "\
+phase init
+
+function f {
+}",
+    },
+    Explanation {
+        code: "CH0010",
+        title: "Backwards phase transition",
+        text: "\
+Phases only move forward. Once a phase has been left, everything bound to
+it has expired and it cannot be re-entered.",
+        failing: "\
+phase init
+phase operate
+
+function f {
+  advance to operate
+  advance to init
+}",
+        fixed: "\
+phase init
+phase operate
+
+function f {
+  advance to operate
+}",
+    },
+    Explanation {
+        code: "CH0011",
+        title: "Skipped phase",
+        text: "\
+A phase transition must move to the phase declared directly after the
+current one. Skipping a phase hides the checks that phase stands for.
+A function body is checked from the phase just before its first transition;
+each call goes through the body's transitions from the caller's phase.",
+        failing: "\
+phase init
+phase operate
+phase teardown
+
+function shutdown {
+  advance to teardown
+}
+
+call shutdown()",
+        fixed: "\
+phase init
+phase operate
+phase teardown
+
+function start {
+  advance to operate
+}
+
+function shutdown {
+  advance to teardown
+}
+
+call start()
+call shutdown()",
+    },
+    Explanation {
+        code: "CH0012",
+        title: "Capability conflict",
+        text: "\
+UniqueMut grants exclusive mutable access. It cannot coexist with any other
+live capability on the same value, and no other capability can be granted
+while it is live. Own and ThreadSend are exclusive in the same way. To share
+a value read-only while holding one of them, reborrow with `from` or
+downgrade it.",
+        failing: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let buffer in s
+  capability UniqueMut buffer during l
+  capability SharedRead buffer during l
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let buffer in s
+  capability SharedRead buffer during l
+  capability SharedRead buffer during l
+}",
+    },
+    Explanation {
+        code: "CH0013",
+        title: "Capability on a destroyed value",
+        text: "\
+A capability was granted on a value that has already been destroyed,
+either by `drop` or because its owning scope ended.",
+        failing: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  drop v
+  capability SharedRead v during l
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability SharedRead v during l
+}",
+    },
+    Explanation {
+        code: "CH0014",
+        title: "Inactive lifetime",
+        text: "\
+A capability was granted during a lifetime that has already ended.
+
+Lifetime names are lexically scoped and expire with the phase they were
+created in, so an ended lifetime is normally reported as out of scope
+(CH0024) or as belonging to another phase (CH0015). This check is the
+backstop for any lifetime that is still nameable after it has ended.",
+        failing: "",
+        fixed: "",
+    },
+    Explanation {
+        code: "CH0015",
+        title: "Lifetime used in another phase",
+        text: "\
+Lifetimes belong to the phase they are created in and expire when that
+phase is left. A capability may only be granted during a lifetime of the
+current phase.",
+        failing: "\
+phase init
+phase operate
+
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  advance to operate
+  capability SharedRead v during l
+}",
+        fixed: "\
+phase init
+phase operate
+
+function f {
+  region stack s
+  let v in s
+  advance to operate
+  lifetime l bound to f
+  capability SharedRead v during l
+}",
+    },
+    Explanation {
+        code: "CH0016",
+        title: "Assumption outside unsafe",
+        text: "\
+`assume` records something a human promises is true. Such promises are
+only allowed inside an `unsafe` block, so that every one is auditable.",
+        failing: "\
+function f {
+  assume \"pointer is valid\"
+}",
+        fixed: "\
+function f {
+  unsafe {
+    assume \"pointer is valid\"
+  }
+}",
+    },
+    Explanation {
+        code: "CH0017",
+        title: "Double drop",
+        text: "\
+A value was dropped after it had already been destroyed.",
+        failing: "\
+function f {
+  region stack s
+  let v in s
+  drop v
+  drop v
+}",
+        fixed: "\
+function f {
+  region stack s
+  let v in s
+  drop v
+}",
+    },
+    Explanation {
+        code: "CH0018",
+        title: "Drop of a value not owned by the current scope",
+        text: "\
+Only the scope that owns a value may destroy it. A value allocated in an
+outer scope cannot be dropped from an inner block.",
+        failing: "\
+function f {
+  region stack s
+  let v in s
+  unsafe {
+    drop v
+  }
+}",
+        fixed: "\
+function f {
+  region stack s
+  let v in s
+  drop v
+}",
+    },
+    Explanation {
+        code: "CH0019",
+        title: "Drop with active capabilities",
+        text: "\
+A value may only be destroyed after all capabilities on it have expired or
+been released. Dropping it earlier would leave those capabilities pointing
+at freed memory. Use `release` to end a capability before its lifetime does.",
+        failing: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability SharedRead v during l
+  drop v
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability SharedRead v during l
+  release SharedRead v
+  drop v
+}",
+    },
+    Explanation {
+        code: "CH0020",
+        title: "No active scope",
+        text: "\
+Internal error: a statement ran with no enclosing scope. Every program
+runs inside the module scope, so this indicates a verifier bug.",
+        failing: "",
+        fixed: "",
+    },
+    Explanation {
+        code: "CH0021",
+        title: "Lifetime bound to an unknown scope",
+        text: "\
+The scope named in `lifetime <name> bound to <scope>` does not exist.
+A lifetime must be bound to an enclosing function (or `module`), so a typo
+in the scope name is an error rather than being silently accepted.",
+        failing: "\
+function process_payment {
+  lifetime request bound to proces_payment
+}",
+        fixed: "\
+function process_payment {
+  lifetime request bound to process_payment
+}",
+    },
+    Explanation {
+        code: "CH0022",
+        title: "Lifetime bound to a non-enclosing scope",
+        text: "\
+The scope named in `lifetime <name> bound to <scope>` exists, but the
+statement is not inside it. A lifetime can only be bound to a scope that
+encloses its declaration; binding it elsewhere would let it outlive or
+precede the scope it claims to belong to.",
+        failing: "\
+function setup {
+}
+
+function run {
+  lifetime l bound to setup
+}",
+        fixed: "\
+function setup {
+}
+
+function run {
+  lifetime l bound to run
+}",
+    },
+    Explanation {
+        code: "CH0023",
+        title: "Duplicate declaration",
+        text: "\
+A lifetime, region or value name was declared twice in the same scope.
+Redeclaring would silently replace the first binding. A nested scope may
+shadow an outer name, but within one scope every name is declared once.",
+        failing: "\
+function f {
+  region stack s
+  let buffer in s
+  let buffer in s
+}",
+        fixed: "\
+function f {
+  region stack s
+  let buffer in s
+  let scratch in s
+}",
+    },
+    Explanation {
+        code: "CH0024",
+        title: "Name used outside its scope",
+        text: "\
+A name was used after the scope that declared it exited. Names are visible
+only in the scope that declares them and in scopes nested inside it, so a
+value or region declared in one function is not visible in the next.",
+        failing: "\
+function setup {
+  region stack s
+}
+
+function run {
+  let buffer in s
+}",
+        fixed: "\
+function run {
+  region stack s
+  let buffer in s
+}",
+    },
+    Explanation {
+        code: "CH0025",
+        title: "Value used in spawn without a thread capability",
+        text: "\
+A `spawn` block runs concurrently with the code that started it. Any value
+it uses from an enclosing scope crosses a thread boundary and must be
+handed over explicitly: ThreadSend moves exclusive access to the spawn,
+ThreadShare lets several spawns read it at once.",
+        failing: "\
+function worker {
+  lifetime run bound to worker
+  region stack pool
+  let config in pool
+  spawn {
+    capability SharedRead config during run
+  }
+}",
+        fixed: "\
+function worker {
+  lifetime run bound to worker
+  region stack pool
+  let config in pool
+  capability ThreadShare config during run
+  spawn {
+    capability SharedRead config during run
+  }
+}",
+    },
+    Explanation {
+        code: "CH0026",
+        title: "Data race between spawns",
+        text: "\
+Spawns started in the same scope are concurrently live until that scope
+ends. Two of them may share a value only if every access is shared; if one
+of them has exclusive access (UniqueMut, or the value was handed over with
+ThreadSend) any other access from a concurrent spawn is a data race.",
+        failing: "\
+function workers {
+  lifetime run bound to workers
+  region stack pool
+  let ledger in pool
+  capability ThreadSend ledger during run
+  spawn {
+    capability UniqueMut ledger during run
+  }
+  spawn {
+    capability SharedRead ledger during run
+  }
+}",
+        fixed: "\
+function workers {
+  lifetime run bound to workers
+  region stack pool
+  let ledger in pool
+  capability ThreadSend ledger during run
+  spawn {
+    capability UniqueMut ledger during run
+  }
+}",
+    },
+    Explanation {
+        code: "CH0027",
+        title: "Use after free",
+        text: "\
+A value was read, written or passed to a call after it had been destroyed,
+either by `drop` or because its owning scope ended.",
+        failing: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  drop v
+  read v
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability SharedRead v during l
+  read v
+}",
+    },
+    Explanation {
+        code: "CH0028",
+        title: "Access without a capability",
+        text: "\
+Every access to a value must be covered by a live capability. Allocating a
+value does not grant access to it; state how it may be used with
+`capability <kind> <value> during <lifetime>` first.",
+        failing: "\
+function f {
+  region stack s
+  let v in s
+  read v
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability SharedRead v during l
+  read v
+}",
+    },
+    Explanation {
+        code: "CH0029",
+        title: "Access after the capability's lifetime expired",
+        text: "\
+The value was accessed after the lifetime of its capability ended, for
+example because the phase the lifetime belongs to was left. Grant a new
+capability during a lifetime of the current phase before accessing it.",
+        failing: "\
+phase init
+phase operate
+
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability SharedRead v during l
+  advance to operate
+  read v
+}",
+        fixed: "\
+phase init
+phase operate
+
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability SharedRead v during l
+  read v
+  advance to operate
+  lifetime m bound to f
+  capability SharedRead v during m
+  read v
+}",
+    },
+    Explanation {
+        code: "CH0030",
+        title: "Write without UniqueMut",
+        text: "\
+Writing to a value requires exclusive mutable access. SharedRead and other
+shared capabilities only permit reading.",
+        failing: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability SharedRead v during l
+  write v
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability UniqueMut v during l
+  write v
+}",
+    },
+    Explanation {
+        code: "CH0031",
+        title: "Move target is not an enclosing scope",
+        text: "\
+A value can only be moved outward, to a function, block or unsafe scope
+that encloses the move statement. Moving into a sibling or an unknown scope
+would leave the value without an owner that is guaranteed to outlive it.",
+        failing: "\
+function outer {
+  region stack s
+  function inner {
+    let v in s
+    move v to elsewhere
+  }
+}",
+        fixed: "\
+function outer {
+  region stack s
+  function inner {
+    let v in s
+    move v to outer
+  }
+}",
+    },
+    Explanation {
+        code: "CH0032",
+        title: "Use after move",
+        text: "\
+After `move`, ownership of the value belongs to the target scope. The scope
+it was moved out of may no longer use the name.",
+        failing: "\
+function outer {
+  lifetime l bound to outer
+  region stack s
+  function inner {
+    let v in s
+    move v to outer
+    capability SharedRead v during l
+  }
+}",
+        fixed: "\
+function outer {
+  lifetime l bound to outer
+  region stack s
+  function inner {
+    let v in s
+    move v to outer
+  }
+  call inner()
+  capability SharedRead v during l
+}",
+    },
+    Explanation {
+        code: "CH0033",
+        title: "Move of a value not owned by the current scope",
+        text: "\
+Only the scope that owns a value may transfer its ownership. A nested scope
+that can see the value through the scope chain does not own it.",
+        failing: "\
+function outer {
+  region stack s
+  let v in s
+  unsafe {
+    move v to outer
+  }
+}",
+        fixed: "\
+function outer {
+  region stack s
+  function inner {
+    let v in s
+    move v to outer
+  }
+}",
+    },
+    Explanation {
+        code: "CH0034",
+        title: "Move with active capabilities",
+        text: "\
+A value cannot change owner while capabilities on it are live: those
+capabilities were granted under the old owner. Let them expire first.",
+        failing: "\
+function outer {
+  region stack s
+  function inner {
+    lifetime l bound to inner
+    let v in s
+    capability SharedRead v during l
+    move v to outer
+  }
+}",
+        fixed: "\
+function outer {
+  region stack s
+  function inner {
+    let v in s
+    move v to outer
+  }
+}",
+    },
+    Explanation {
+        code: "CH0035",
+        title: "Unknown capability name",
+        text: "\
+`release NAME` refers to a capability by the name given with `as NAME` when
+it was granted. No capability with that name is visible here.",
+        failing: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability SharedRead v during l as r
+  release reader
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability SharedRead v during l as reader
+  release reader
+}",
+    },
+    Explanation {
+        code: "CH0036",
+        title: "Reference to a capability that is not held",
+        text: "\
+`KIND VALUE` in `release`, `downgrade` or a reborrow's `from` refers to the
+most recently granted live capability of that kind on the value. There is
+none, either because it was never granted or because it has already been
+released, downgraded or has expired.",
+        failing: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability SharedRead v during l
+  release UniqueMut v
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability SharedRead v during l
+  release SharedRead v
+}",
+    },
+    Explanation {
+        code: "CH0037",
+        title: "Reference to a capability that is no longer live",
+        text: "\
+A named capability can be released once. After it has been released, or
+after its lifetime has expired, it can no longer be released, downgraded
+or reborrowed from.",
+        failing: "\
+function f {
+  lifetime l bound to f
+  region heap h
+  let v in h
+  capability UniqueMut v during l as w
+  write v
+  release w
+  release w
+  drop v
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region heap h
+  let v in h
+  capability UniqueMut v during l as w
+  write v
+  release w
+  drop v
+}",
+    },
+    Explanation {
+        code: "CH0038",
+        title: "Invalid downgrade",
+        text: "\
+A capability can only be downgraded along the capability lattice, to a kind
+it includes: Own includes UniqueMut, UniqueMut includes SharedRead, and
+ThreadShare includes SharedRead. Downgrading never grants more access.",
+        failing: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability SharedRead v during l as r
+  downgrade r to UniqueMut
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability UniqueMut v during l as w
+  write v
+  downgrade w to SharedRead
+  read v
+}",
+    },
+    Explanation {
+        code: "CH0039",
+        title: "Reborrow exceeds its parent",
+        text: "\
+A capability reborrowed with `from` may only grant what its parent grants:
+its kind must be included by the parent's kind.",
+        failing: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability SharedRead v during l as r
+  capability UniqueMut v during l from r
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability UniqueMut v during l as w
+  capability SharedRead v during l from w
+}",
+    },
+    Explanation {
+        code: "CH0040",
+        title: "Reborrow outlives its parent",
+        text: "\
+A reborrow's lifetime must end no later than its parent's: it must be bound
+to the parent lifetime's scope or a scope nested inside it.",
+        failing: "\
+function outer {
+  lifetime long bound to outer
+  region stack s
+  let v in s
+  block inner {
+    lifetime short bound to inner
+    capability UniqueMut v during short as w
+    capability SharedRead v during long from w
+  }
+}",
+        fixed: "\
+function outer {
+  region stack s
+  let v in s
+  block inner {
+    lifetime short bound to inner
+    capability UniqueMut v during short as w
+    capability SharedRead v during short from w
+  }
+}",
+    },
+    Explanation {
+        code: "CH0041",
+        title: "Capability suspended by a reborrow",
+        text: "\
+While a capability reborrowed from another is live, the parent is suspended:
+it grants no access and cannot be released or downgraded. Release the
+reborrow, or let it expire, to resume the parent.",
+        failing: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability UniqueMut v during l as w
+  capability SharedRead v during l from w as r
+  write v
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let v in s
+  capability UniqueMut v during l as w
+  capability SharedRead v during l from w as r
+  read v
+  release r
+  write v
+}",
+    },
+    Explanation {
+        code: "CH0042",
+        title: "Reborrow from a capability on another value",
+        text: "\
+A reborrow narrows access to the same value its parent covers. It cannot
+be used to reach a different value.",
+        failing: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let a in s
+  let b in s
+  capability UniqueMut a during l as w
+  capability SharedRead b during l from w
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let a in s
+  let b in s
+  capability UniqueMut a during l as w
+  capability SharedRead a during l from w
+}",
+    },
+    Explanation {
+        code: "CH0043",
+        title: "Heap value leaked at scope exit",
+        text: "\
+Stack values are destroyed implicitly when their owning scope ends. Heap
+values are not: each must be dropped, or moved to an enclosing scope that
+takes responsibility for it, before its owner exits. A heap value still
+owned at that point is a leak.",
+        failing: "\
+function session {
+  region heap buffers
+  let buffer in buffers
+}",
+        fixed: "\
+function session {
+  region heap buffers
+  let buffer in buffers
+  drop buffer
+}",
+    },
+    Explanation {
+        code: "CH0044",
+        title: "Value outlives its region",
+        text: "\
+A region's memory exists only while the scope that declared it is active.
+A value allocated in the region and moved to an owner outside that scope
+would still be alive when the memory goes away, leaving it dangling.
+Declare the region in the scope the value is moved to.",
+        failing: "\
+function outer {
+  function inner {
+    region stack scratch
+    let v in scratch
+    move v to outer
+  }
+}",
+        fixed: "\
+function outer {
+  region stack scratch
+  function inner {
+    let v in scratch
+    move v to outer
+  }
+}",
+    },
+    Explanation {
+        code: "CH0045",
+        title: "Capability outlives its value's region",
+        text: "\
+A capability may not be granted during a lifetime bound to a scope outside
+the one that declared the value's region: the lifetime would still be
+active after the memory it grants access to has gone.",
+        failing: "\
+function outer {
+  lifetime long bound to outer
+  function inner {
+    region stack scratch
+    let v in scratch
+    capability SharedRead v during long
+  }
+}",
+        fixed: "\
+function outer {
+  function inner {
+    lifetime short bound to inner
+    region stack scratch
+    let v in scratch
+    capability SharedRead v during short
+  }
+}",
+    },
+    Explanation {
+        code: "CH0046",
+        title: "Allocation in an inactive region",
+        text: "\
+A value was allocated in a region whose declaring scope has exited.
+
+Region names are lexically scoped, so a region that has gone away is
+normally reported as out of scope (CH0024). This check is the backstop for
+any region that is still nameable after its memory is gone.",
+        failing: "",
+        fixed: "",
+    },
+    Explanation {
+        code: "CH0047",
+        title: "Drop of an external value",
+        text: "\
+External memory belongs to whoever provided it, such as an HSM or a foreign
+library. The program does not free it with `drop`; it hands it back with
+`return VALUE to REGION`, which follows the same ownership and capability
+rules as `drop`.",
+        failing: "\
+function f {
+  region external hsm assume \"HSM provides key memory\"
+  let key in hsm
+  drop key
+}",
+        fixed: "\
+function f {
+  region external hsm assume \"HSM provides key memory\"
+  let key in hsm
+  return key to hsm
+}",
+    },
+    Explanation {
+        code: "CH0048",
+        title: "External value never returned",
+        text: "\
+External values are not destroyed implicitly when their owner exits. Each
+must be handed back with `return VALUE to REGION` before then; otherwise the
+provider's memory is never released.",
+        failing: "\
+function f {
+  region external hsm assume \"HSM provides key memory\"
+  let key in hsm
+}",
+        fixed: "\
+function f {
+  region external hsm assume \"HSM provides key memory\"
+  let key in hsm
+  return key to hsm
+}",
+    },
+    Explanation {
+        code: "CH0049",
+        title: "Return to the wrong region",
+        text: "\
+An external value can only be returned to the region it was allocated in.",
+        failing: "\
+function f {
+  region external hsm assume \"HSM provides key memory\"
+  region external dma assume \"DMA buffers are device-owned\"
+  let key in hsm
+  return key to dma
+}",
+        fixed: "\
+function f {
+  region external hsm assume \"HSM provides key memory\"
+  region external dma assume \"DMA buffers are device-owned\"
+  let key in hsm
+  return key to hsm
+}",
+    },
+    Explanation {
+        code: "CH0050",
+        title: "Return of memory that is not external",
+        text: "\
+`return` hands external memory back to its provider. Stack and heap values
+are the program's own and are destroyed with `drop`; static values are
+never destroyed.",
+        failing: "\
+function f {
+  region heap h
+  let v in h
+  return v to h
+}",
+        fixed: "\
+function f {
+  region heap h
+  let v in h
+  drop v
+}",
+    },
+    Explanation {
+        code: "CH0051",
+        title: "Drop of a static value",
+        text: "\
+Static memory lives for the whole program. Its values survive every scope
+exit and can never be dropped.",
+        failing: "\
+region static tables
+let crc_table in tables
+drop crc_table",
+        fixed: "\
+region static tables
+let crc_table in tables",
+    },
+    Explanation {
+        code: "CH0052",
+        title: "External region declared outside unsafe code",
+        text: "\
+Memory that comes from outside the program cannot be verified, only
+trusted. An external region must therefore be declared inside an `unsafe`
+block, or carry the assumption it is trusted under, which then appears in
+the Safety Report against every value allocated in it.",
+        failing: "\
+function f {
+  region external hsm
+}",
+        fixed: "\
+function f {
+  region external hsm assume \"HSM memory stays mapped for the session\"
+}",
+    },
+    Explanation {
+        code: "CH0053",
+        title: "Secret value destroyed without zeroization",
+        text: "\
+A value declared with `let secret` holds key material. Before it is
+dropped, returned to its region or destroyed at scope exit it must be wiped
+with `zeroize`, which needs a capability that includes UniqueMut. Any later
+write, or any new write capability, means it must be zeroized again.",
+        failing: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let secret key in s
+  capability UniqueMut key during l as w
+  write key
+  release w
+  drop key
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let secret key in s
+  capability UniqueMut key during l as w
+  write key
+  zeroize key
+  release w
+  drop key
+}",
+    },
+    Explanation {
+        code: "CH0054",
+        title: "Information flow to a lower label",
+        text: "\
+Regions and values carry a security label, public or secret. A region is
+labelled with `label secret`; a value takes its region's label, or secret
+when declared with `let secret`. Secret data may not flow into a public
+value, by `copy` or by deriving a value with `let ... from`, nor through a
+call whose function copies one parameter into another. If such a flow is
+intended, wrap it in `declassify` inside an unsafe block that states the
+assumption making it acceptable.",
+        failing: "\
+function f {
+  lifetime l bound to f
+  region stack keys label secret
+  region stack logs
+  let key in keys
+  let log in logs
+  capability SharedRead key during l
+  capability UniqueMut log during l
+  copy key into log
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region stack keys label secret
+  region stack logs
+  let key in keys
+  let log in logs
+  capability SharedRead key during l
+  capability UniqueMut log during l
+  unsafe {
+    assume \"Only the key check value is written to the log\"
+    declassify {
+      copy key into log
+    }
+  }
+}",
+    },
+    Explanation {
+        code: "CH0055",
+        title: "Declassify outside unsafe code",
+        text: "\
+Declassification deliberately breaks the information flow rules, so it is
+only allowed inside an `unsafe` block, where the assumption that justifies
+it is recorded.",
+        failing: "\
+function f {
+  declassify {
+  }
+}",
+        fixed: "\
+function f {
+  unsafe {
+    assume \"Nothing secret leaves this block\"
+    declassify {
+    }
+  }
+}",
+    },
+    Explanation {
+        code: "CH0056",
+        title: "Declassification without an assumption",
+        text: "\
+Every declassification point is listed in the Safety Report with the
+assumptions it rests on. The unsafe block enclosing `declassify` must state
+at least one with `assume`.",
+        failing: "\
+function f {
+  lifetime l bound to f
+  region stack keys label secret
+  region stack logs
+  let key in keys
+  capability SharedRead key during l
+  unsafe {
+    declassify {
+      let kcv from key in logs
+    }
+  }
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region stack keys label secret
+  region stack logs
+  let key in keys
+  capability SharedRead key during l
+  unsafe {
+    assume \"A key check value reveals nothing about the key\"
+    declassify {
+      let kcv from key in logs
+    }
+  }
+}",
+    },
+    Explanation {
+        code: "CH0057",
+        title: "Unknown security label",
+        text: "\
+Regions can be labelled `public` (the default) or `secret`.",
+        failing: "\
+region stack keys label confidential",
+        fixed: "\
+region stack keys label secret",
+    },
+    Explanation {
+        code: "CH0058",
+        title: "Unknown function",
+        text: "\
+`call f(...)` must name a function declared in the current scope or an
+enclosing one, before or after the call. A function called before its
+declaration is verified at the call and sees what its scope had declared
+by then. To call code outside the program, use the opaque form
+`call f with a, b`, which only reads its arguments.",
+        failing: "\
+function main {
+  call sign()
+}",
+        fixed: "\
+function sign {
+}
+
+function main {
+  call sign()
+}",
+    },
+    Explanation {
+        code: "CH0059",
+        title: "Wrong number of arguments",
+        text: "\
+A call passes exactly one argument per parameter in the function's
+signature.",
+        failing: "\
+function sign(key: SharedRead) {
+  read key
+}
+
+function main {
+  call sign()
+}",
+        fixed: "\
+function sign(key: SharedRead) {
+  read key
+}
+
+function main {
+  lifetime l bound to main
+  region stack keys
+  let key in keys
+  capability SharedRead key during l
+  call sign(key)
+}",
+    },
+    Explanation {
+        code: "CH0060",
+        title: "Argument without the required capability",
+        text: "\
+Each parameter names the capability the function needs on its argument.
+The body is verified once against that signature, so the caller must hold
+a live, unsuspended capability that includes it for the duration of the
+call. An Own parameter takes the value: the caller can no longer use it.",
+        failing: "\
+function fill(buf: UniqueMut) {
+  write buf
+}
+
+function main {
+  lifetime l bound to main
+  region stack pool
+  let buf in pool
+  capability SharedRead buf during l
+  call fill(buf)
+}",
+        fixed: "\
+function fill(buf: UniqueMut) {
+  write buf
+}
+
+function main {
+  lifetime l bound to main
+  region stack pool
+  let buf in pool
+  capability UniqueMut buf during l
+  call fill(buf)
+}",
+    },
+    Explanation {
+        code: "CH0061",
+        title: "Aliased argument",
+        text: "\
+A value passed to an Own, UniqueMut or ThreadSend parameter cannot also be
+passed to another parameter of the same call: the function relies on that
+parameter having the value to itself.",
+        failing: "\
+function copy_into(src: SharedRead, dst: UniqueMut) {
+  copy src into dst
+}
+
+function main {
+  lifetime l bound to main
+  region stack pool
+  let buf in pool
+  capability UniqueMut buf during l
+  call copy_into(buf, buf)
+}",
+        fixed: "\
+function copy_into(src: SharedRead, dst: UniqueMut) {
+  copy src into dst
+}
+
+function main {
+  lifetime l bound to main
+  region stack pool
+  let buf in pool
+  let out in pool
+  capability SharedRead buf during l
+  capability UniqueMut out during l
+  call copy_into(buf, out)
+}",
+    },
+    Explanation {
+        code: "CH0062",
+        title: "Recursive call",
+        text: "\
+Each function body is verified once, and every call applies the summary
+that verification produced: the capabilities the function requires, the
+values it consumes or moves, and the assumptions it depends on. A call to a
+function whose body is still being verified, directly, through a nested
+function or through other calls, has no complete summary to apply.",
+        failing: "\
+function countdown {
+  call countdown()
+}",
+        fixed: "\
+function tick {
+}
+
+function countdown {
+  call tick()
+}",
+    },
+    Explanation {
+        code: "CH0063",
+        title: "Inconsistent branches",
+        text: "\
+Both paths of an `if` are verified, each on its own copy of the state, and
+then joined. A value the enclosing scope owns must be kept on both paths or
+given up on both, by `drop`, `return`, `move` or an Own call. Dropping it
+on one path only leaves the code after the `if` unsure whether it exists.
+Each path runs in a block of its own that may drop and move what the
+enclosing scope owns. A value a path creates and moves out to an enclosing
+scope must be created there on the other path too, with the same name; the
+two then become one value. Created on one path only, it cannot be named
+after the `if`.",
+        failing: "\
+function handle {
+  region stack pool
+  let buf in pool
+  if failed {
+    drop buf
+  }
+}",
+        fixed: "\
+function handle {
+  region stack pool
+  let buf in pool
+  if failed {
+    drop buf
+  } else {
+    drop buf
+  }
+}",
+    },
+    Explanation {
+        code: "CH0064",
+        title: "Branches end in different phases",
+        text: "\
+After an `if`, the program must be in one known phase. Advance on both
+paths or on neither.",
+        failing: "\
+phase init
+phase operate
+
+function f {
+  if ready {
+    advance to operate
+  }
+}",
+        fixed: "\
+phase init
+phase operate
+
+function f {
+  if ready {
+  }
+  advance to operate
+}",
+    },
+    Explanation {
+        code: "CH0065",
+        title: "Loop diverges",
+        text: "\
+`loop { }` and `repeat n { }` are verified by running the body until it
+leaves behind a state an earlier iteration did: the same values alive,
+zeroized and owned by the same scopes, and the same capabilities held.
+Each later iteration would repeat one already verified. Each iteration
+runs in a block of its own, so its lifetimes and grants end with it, and
+each value the body allocates must be dropped, returned or moved off by
+the end of the iteration. A value still alive or leaked after every
+iteration piles up, and the state never repeats; the report names the
+first iteration at which it did.",
+        failing: "\
+function serve {
+  region static table
+  loop {
+    let entry in table
+  }
+}",
+        fixed: "\
+function serve {
+  region stack scratch
+  loop {
+    let entry in scratch
+  }
+}",
+    },
+    Explanation {
+        code: "CH0066",
+        title: "Value captured by a function",
+        text: "\
+A function body is verified once, against its parameter list, and its
+summary is applied at every call. A value or capability declared outside
+the function would be checked against whatever state it had where the
+function was declared rather than where it is called, so a body may only
+use the values its parameters pass in. Regions, lifetimes and functions of
+enclosing scopes stay visible.",
+        failing: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let buf in s
+  capability UniqueMut buf during l as c
+  function touch {
+    write buf
+  }
+  call touch()
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region stack s
+  let buf in s
+  capability UniqueMut buf during l as c
+  function touch(data: UniqueMut) {
+    write data
+  }
+  call touch(buf)
+}",
+    },
+    Explanation {
+        code: "CH0067",
+        title: "Branches hand a value to different owners",
+        text: "\
+A value given up on both paths of an `if` may still be owed afterwards: a
+`move` to an enclosing scope hands it to a scope that outlives the `if`,
+while a `drop` ends it. The code after the `if` cannot tell whether that
+scope owns the value, so both paths must leave it with the same owner. The
+scope it was handed to on one path still answers for it, so a heap value
+it never drops is reported as leaked as well.",
+        failing: "\
+function outer {
+  region heap h
+  block inner {
+    let buf in h
+    if ok {
+      move buf to outer
+    } else {
+      drop buf
+    }
+  }
+  drop buf
+}",
+        fixed: "\
+function outer {
+  region heap h
+  block inner {
+    let buf in h
+    if ok {
+      move buf to outer
+    } else {
+      move buf to outer
+    }
+  }
+  drop buf
+}",
+    },
+    Explanation {
+        code: "CH0068",
+        title: "Capability released on one path only",
+        text: "\
+The capabilities held after an `if` must not depend on which path ran. A
+capability granted before the `if` and released on one path only would
+still be live after the other, so a later `drop` or `move` of the value
+could not be checked for both. Release it on both paths, or on neither.",
+        failing: "\
+function f {
+  lifetime l bound to f
+  region heap h
+  let buf in h
+  capability UniqueMut buf during l as w
+  if ok {
+    release w
+  }
+  drop buf
+}",
+        fixed: "\
+function f {
+  lifetime l bound to f
+  region heap h
+  let buf in h
+  capability UniqueMut buf during l as w
+  if ok {
+    write buf
+  }
+  release w
+  drop buf
+}",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_skips_empty_examples() {
+        let text = render(lookup("CH0020").unwrap());
+        assert!(!text.contains("Failing example:"));
+        assert!(!text.contains("Fixed example:"));
+
+        let text = render(lookup("CH0001").unwrap());
+        assert!(text.contains("Failing example:") && text.contains("Fixed example:"));
+    }
+}
@@ -1,94 +1,162 @@
-use std::collections::HashMap;
-
-pub type ValueId = u64;
-pub type RegionId = u64;
-pub type LifetimeId = u64;
-pub type CapabilityId = u64;
-pub type ScopeId = u64;
-pub type AssumptionId = u64;
-
-#[derive(Debug)]
-#[derive(Clone)]
-
-pub struct ValueNode {
-    pub id: ValueId,
-    pub region: RegionId,
-    pub alive: bool,
-    pub origin: ValueOrigin,
-}
-
-#[derive(Debug)]
-#[derive(Clone)]
-pub enum ValueOrigin {
-    Safe,
-    Unsafe,
-}
-
-#[derive(Debug)]
-pub struct RegionNode {
-    pub id: RegionId,
-    pub kind: RegionKind,
-    pub scope: ScopeId,
-}
-
-#[derive(Debug)]
-pub enum RegionKind {
-    Stack,
-    Heap,
-    External,
-    Static,
-}
-
-#[derive(Debug)]
-pub struct LifetimeNode {
-    pub id: LifetimeId,
-    pub scope: ScopeId,
-    pub active: bool,
-    pub(crate) phase: usize,
-}
-
-#[derive(Debug)]
-pub struct CapabilityNode {
-    pub id: CapabilityId,
-    pub kind: CapabilityKind,
-    pub value: ValueId,
-    pub lifetime: LifetimeId,
-    pub scope: ScopeId,
-    pub(crate) phase: usize,
-}
-
-#[derive(Debug, PartialEq, Eq)]
-pub enum CapabilityKind {
-    Own,
-    SharedRead,
-    UniqueMut,
-    ThreadSend,
-    ThreadShare,
-}
-
-#[derive(Debug)]
-#[derive(Clone)]
-#[derive(serde::Serialize)]
-pub struct UnsafeAssumptionNode {
-    pub id: AssumptionId,
-    pub description: String,
-    pub scope: ScopeId,
-    pub affected_values: Vec<ValueId>,
-    pub(crate) phase: usize,
-}
-#[derive(Debug)]
-pub struct OwnershipEdge {
-    pub value: ValueId,
-    pub owner: ScopeId,
-}
-
-#[derive(Debug)]
-pub struct ConstraintGraph {
-    pub values: HashMap<ValueId, ValueNode>,
-    pub regions: HashMap<RegionId, RegionNode>,
-    pub lifetimes: HashMap<LifetimeId, LifetimeNode>,
-    pub capabilities: HashMap<CapabilityId, CapabilityNode>,
-    pub unsafe_assumptions: HashMap<AssumptionId, UnsafeAssumptionNode>,
-
-    pub ownership_edges: Vec<OwnershipEdge>,
-}
+use std::collections::HashMap;
+
+use crate::ast::Span;
+
+pub type ValueId = u64;
+pub type RegionId = u64;
+pub type LifetimeId = u64;
+pub type CapabilityId = u64;
+pub type ScopeId = u64;
+pub type AssumptionId = u64;
+
+#[derive(Debug)]
+#[derive(Clone)]
+
+pub struct ValueNode {
+    pub id: ValueId,
+    pub name: String,
+    pub region: RegionId,
+    /// The scope the value was allocated in.
+    pub scope: ScopeId,
+    pub alive: bool,
+    /// Set when a heap value was still owned as its last owner exited.
+    pub leaked: bool,
+    pub origin: ValueOrigin,
+    /// The values this one was derived from with `let ... from`.
+    pub derived_from: Vec<ValueId>,
+    /// Declared with `let secret`: must be zeroized before destruction.
+    pub secret: bool,
+    /// True once zeroized and not written or writable since.
+    pub zeroized: bool,
+    /// The region's label, raised to Secret by `let secret`.
+    pub label: Label,
+    /// Set for the placeholder a parameter is bound to in a function body;
+    /// it stands for each caller's argument.
+    pub parameter: bool,
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum ValueOrigin {
+    Safe,
+    Unsafe,
+}
+
+#[derive(Debug, Clone)]
+pub struct RegionNode {
+    pub id: RegionId,
+    pub name: String,
+    pub kind: RegionKind,
+    pub scope: ScopeId,
+    /// False once the declaring scope has exited and the memory is gone.
+    pub active: bool,
+    pub label: Label,
+}
+
+/// Security label of a region or value. Information may flow from a lower
+/// label to a higher one, never the other way without `declassify`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Label {
+    Public,
+    Secret,
+}
+
+#[derive(Debug, Clone)]
+pub enum RegionKind {
+    Stack,
+    Heap,
+    External,
+    Static,
+}
+
+#[derive(Debug, Clone)]
+pub struct LifetimeNode {
+    pub name: String,
+    pub scope: ScopeId,
+    pub active: bool,
+    pub(crate) phase: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct CapabilityNode {
+    pub id: CapabilityId,
+    pub kind: CapabilityKind,
+    pub value: ValueId,
+    pub lifetime: LifetimeId,
+    pub scope: ScopeId,
+    pub(crate) phase: usize,
+    /// The capability this one was reborrowed from. The parent is
+    /// suspended for as long as this capability is live.
+    pub parent: Option<CapabilityId>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapabilityKind {
+    Own,
+    SharedRead,
+    UniqueMut,
+    ThreadSend,
+    ThreadShare,
+}
+
+impl CapabilityKind {
+    /// Exclusive capabilities cannot coexist with any other capability on
+    /// the same value. Own includes UniqueMut, and ThreadSend hands the
+    /// value to another thread, so both are exclusive just like UniqueMut.
+    pub fn is_exclusive(self) -> bool {
+        matches!(
+            self,
+            CapabilityKind::Own | CapabilityKind::UniqueMut | CapabilityKind::ThreadSend
+        )
+    }
+
+    /// The capability lattice: Own ⊇ UniqueMut ⊇ SharedRead, and
+    /// ThreadShare ⊇ SharedRead. Every kind includes itself.
+    ///
+    /// A capability can be downgraded, or reborrowed, to any kind it includes.
+    pub fn includes(self, other: CapabilityKind) -> bool {
+        use CapabilityKind::*;
+
+        self == other
+            || matches!(
+                (self, other),
+                (Own, UniqueMut)
+                    | (Own, SharedRead)
+                    | (UniqueMut, SharedRead)
+                    | (ThreadShare, SharedRead)
+            )
+    }
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(serde::Serialize)]
+pub struct UnsafeAssumptionNode {
+    pub id: AssumptionId,
+    pub description: String,
+    pub scope: ScopeId,
+    pub affected_values: Vec<ValueId>,
+    /// True when no `affects` clause was given and the affected values were
+    /// taken from the enclosing unsafe block.
+    pub inferred: bool,
+    pub(crate) phase: usize,
+    /// The statement that states it.
+    #[serde(skip)]
+    pub(crate) span: Span,
+}
+#[derive(Debug, Clone)]
+pub struct OwnershipEdge {
+    pub value: ValueId,
+    pub owner: ScopeId,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConstraintGraph {
+    pub values: HashMap<ValueId, ValueNode>,
+    pub regions: HashMap<RegionId, RegionNode>,
+    pub lifetimes: HashMap<LifetimeId, LifetimeNode>,
+    pub capabilities: HashMap<CapabilityId, CapabilityNode>,
+    pub unsafe_assumptions: HashMap<AssumptionId, UnsafeAssumptionNode>,
+
+    pub ownership_edges: Vec<OwnershipEdge>,
+}
@@ -1,374 +1,2061 @@
-use crate::graph::*;
-use crate::scope::*;
-use std::collections::HashMap;
-use crate::scope::{ScopeKind, ScopeNode};
-use crate::graph::{RegionNode, RegionKind};
-
-pub type PhaseId = usize;
-
-#[derive(Debug)]
-pub struct Phase {
-    pub id: PhaseId,
-    pub name: String,
-    pub order: usize,
-}
-
-pub struct Capability {
-    pub value_id: ValueId,
-    pub kind: CapabilityKind,
-    pub phase: PhaseId,
-    pub active: bool,
-}
-
-pub struct Lifetime {
-    pub id: LifetimeId,
-    pub phase: PhaseId,
-    pub active: bool,
-}
-
-pub struct UnsafeAssumption {
-    pub id: String,
-    pub phase: PhaseId,
-    pub scope: ScopeId,
-    pub values: Vec<ValueId>,
-}
-
-
-
-pub struct InterpreterState {
-    pub graph: ConstraintGraph,
-    pub scopes: HashMap<ScopeId, ScopeNode>,
-    pub scope_stack: Vec<ScopeId>,
-
-    next_value_id: ValueId,
-    next_region_id: RegionId,
-    next_lifetime_id: LifetimeId,
-    next_capability_id: CapabilityId,
-    next_scope_id: ScopeId,
-    next_assumption_id: AssumptionId,
-    pub current_phase: Option<PhaseId>,
-    pub phases: Vec<Phase>,
-}
-
-impl InterpreterState {
-    pub fn new() -> Self {
-        InterpreterState {
-            graph: ConstraintGraph {
-                values: HashMap::new(),
-                regions: HashMap::new(),
-                lifetimes: HashMap::new(),
-                capabilities: HashMap::new(),
-                unsafe_assumptions: HashMap::new(),
-                ownership_edges: Vec::new(),
-            },
-            scopes: HashMap::new(),
-            scope_stack: Vec::new(),
-            next_value_id: 1,
-            next_region_id: 1,
-            next_lifetime_id: 1,
-            next_capability_id: 1,
-            next_scope_id: 1,
-            next_assumption_id: 1,
-            phases: Vec::new(),
-            current_phase: None,
-        }
-    }
-
-    // =====================
-    // PHASE MANAGEMENT
-    // =====================
-
-    pub fn declare_phase(&mut self, name: String) -> Result<PhaseId, String> {
-        if self.phases.iter().any(|p| p.name == name) {
-            return Err(format!("Duplicate phase declaration: {}", name));
-        }
-
-        let id = self.phases.len();
-        self.phases.push(Phase {
-            id,
-            name,
-            order: id,
-        });
-
-        if self.current_phase.is_none() {
-            self.current_phase = Some(id);
-        }
-
-        Ok(id)
-    }
-
-    pub fn current_phase(&self) -> PhaseId {
-        self.current_phase.expect("No active phase")
-    }
-
-    pub fn current_phase_name(&self) -> &str {
-        &self.phases[self.current_phase()].name
-    }
-
-    // =====================
-    // SCOPE MANAGEMENT
-    // =====================
-
-    fn fresh_scope_id(&mut self) -> ScopeId {
-        let id = self.next_scope_id;
-        self.next_scope_id += 1;
-        id
-    }
-
-    pub fn current_scope(&self) -> ScopeId {
-        *self.scope_stack.last().expect("No active scope")
-    }
-
-    pub fn enter_scope(&mut self, kind: ScopeKind) -> ScopeId {
-        let id = self.fresh_scope_id();
-        let parent = self.scope_stack.last().copied();
-
-        let scope = ScopeNode {
-            id,
-            parent,
-            kind,
-            active: true,
-        };
-
-        self.scopes.insert(id, scope);
-        self.scope_stack.push(id);
-        id
-    }
-
-    pub fn exit_scope(&mut self) -> Result<(), String> {
-        let scope_id = self.scope_stack.pop()
-            .ok_or("Attempted to exit scope, but no scope is active")?;
-
-        // Expire lifetimes in this scope
-        for lifetime in self.graph.lifetimes.values_mut() {
-            if lifetime.scope == scope_id {
-                lifetime.active = false;
-            }
-        }
-
-        // Expire capabilities in this scope
-        self.graph.capabilities.retain(|_, cap| cap.scope != scope_id);
-
-        // Destroy owned values
-        let owned_values: Vec<ValueId> = self.graph.ownership_edges
-            .iter()
-            .filter(|edge| edge.owner == scope_id)
-            .map(|edge| edge.value)
-            .collect();
-
-        for value_id in owned_values {
-            let value = self.graph.values.get_mut(&value_id).unwrap();
-            value.alive = false;
-        }
-
-        self.graph.ownership_edges.retain(|e| e.owner != scope_id);
-        self.scopes.get_mut(&scope_id).unwrap().active = false;
-
-        Ok(())
-    }
-
-    // =====================
-    // VALUE / REGION
-    // =====================
-
-    pub fn declare_region(&mut self, kind: RegionKind) -> Result<RegionId, String> {
-        let scope_id = self.current_scope();
-        let id = self.next_region_id;
-        self.next_region_id += 1;
-
-        self.graph.regions.insert(id, RegionNode {
-            id,
-            kind,
-            scope: scope_id,
-        });
-
-        Ok(id)
-    }
-
-    pub fn allocate_value(&mut self, region: RegionId) -> Result<ValueId, String> {
-        let id = self.next_value_id;
-        self.next_value_id += 1;
-
-        self.graph.values.insert(id, ValueNode {
-            id,
-            region,
-            alive: true,
-            origin: if self.is_in_unsafe_scope() {
-                ValueOrigin::Unsafe
-            } else {
-                ValueOrigin::Safe
-            },
-        });
-
-        self.graph.ownership_edges.push(OwnershipEdge {
-            value: id,
-            owner: self.current_scope(),
-        });
-
-        Ok(id)
-    }
-
-    // =====================
-    // LIFETIMES (PHASE-BOUND)
-    // =====================
-
-    pub fn create_lifetime(
-        &mut self,
-        scope: ScopeId,
-        phase: PhaseId,
-    ) -> Result<LifetimeId, String> {
-        let id = self.next_lifetime_id;
-        self.next_lifetime_id += 1;
-
-        self.graph.lifetimes.insert(id, LifetimeNode {
-            id,
-            scope,
-            phase,
-            active: true,
-        });
-
-        Ok(id)
-    }
-
-    // =====================
-    // CAPABILITIES (PHASE-BOUND)
-    // =====================
-
-    pub fn create_capability(
-        &mut self,
-        kind: CapabilityKind,
-        value: ValueId,
-        lifetime: LifetimeId,
-        phase: PhaseId,
-    ) -> Result<CapabilityId, String> {
-
-        let value_node = self.graph.values.get(&value)
-            .ok_or("Capability refers to non-existent value")?;
-        if !value_node.alive {
-            return Err("Cannot create capability for destroyed value".into());
-        }
-
-        let lifetime_node = self.graph.lifetimes.get(&lifetime)
-            .ok_or("Capability refers to non-existent lifetime")?;
-        if !lifetime_node.active {
-            return Err("Cannot create capability with inactive lifetime".into());
-        }
-
-        if lifetime_node.phase != phase {
-            return Err(format!(
-                "Lifetime phase violation: lifetime created in phase `{}`, used in phase `{}`",
-                self.phases[lifetime_node.phase].name,
-                self.current_phase_name()
-            ));
-        }
-
-        for cap in self.graph.capabilities.values() {
-            if cap.value == value {
-                if matches!(cap.kind, CapabilityKind::UniqueMut)
-                    || matches!(kind, CapabilityKind::UniqueMut)
-                {
-                    return Err("Capability conflict: UniqueMut requires exclusivity".into());
-                }
-            }
-        }
-
-        let id = self.next_capability_id;
-        self.next_capability_id += 1;
-
-        self.graph.capabilities.insert(id, CapabilityNode {
-            id,
-            kind,
-            value,
-            lifetime,
-            scope: self.current_scope(),
-            phase,
-        });
-
-        Ok(id)
-    }
-
-    // =====================
-    // UNSAFE ASSUMPTIONS (PHASE-BOUND)
-    // =====================
-
-    pub fn add_unsafe_assumption(
-        &mut self,
-        description: String,
-        phase: PhaseId,
-        affected_values: Vec<ValueId>,
-    ) -> Result<AssumptionId, String> {
-
-        if !self.is_in_unsafe_scope() {
-            return Err("Unsafe assumptions must be declared inside unsafe scope".into());
-        }
-
-        let id = self.next_assumption_id;
-        self.next_assumption_id += 1;
-
-        self.graph.unsafe_assumptions.insert(id, UnsafeAssumptionNode {
-            id,
-            description,
-            scope: self.current_scope(),
-            phase,
-            affected_values,
-        });
-
-        Ok(id)
-    }
-
-    // =====================
-    // HELPERS
-    // =====================
-
-    fn is_in_unsafe_scope(&self) -> bool {
-        self.scope_stack.iter().any(|id| {
-            matches!(self.scopes[id].kind, ScopeKind::Unsafe)
-        })
-    }
-
-    pub fn drop_value(&mut self, value: ValueId) -> Result<(), String> {
-    let scope_id = *self.scope_stack
-        .last()
-        .ok_or("No active scope for drop")?;
-
-    {
-        let value_node = self.graph.values.get(&value)
-            .ok_or("Attempted to drop non-existent value")?;
-
-        if !value_node.alive {
-            return Err("Attempted to drop value that is already destroyed".into());
-        }
-    }
-
-    // Ownership check
-    if !self.graph.ownership_edges.iter().any(|e| {
-        e.value == value && e.owner == scope_id
-    }) {
-        return Err("Cannot drop value not owned by current scope".into());
-    }
-
-    // Capability check (regardless of phase)
-    let has_active_caps = self.graph.capabilities.values().any(|cap| {
-        cap.value == value
-    });
-
-    if has_active_caps {
-        return Err(format!(
-            "Cannot drop value {} because active capabilities exist.\n\
-             Rule: A value may only be destroyed after all capabilities expire.",
-            value
-        ));
-    }
-
-    // Destroy value
-    let value_node = self.graph.values.get_mut(&value)
-        .ok_or("Attempted to drop non-existent value")?;
-    value_node.alive = false;
-
-    // Remove ownership edges
-    self.graph.ownership_edges.retain(|edge| edge.value != value);
-
-    Ok(())
-}
-
-}
\ No newline at end of file
+use crate::graph::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+use crate::scope::{ScopeKind, ScopeNode};
+use crate::graph::{RegionNode, RegionKind};
+use crate::ast::Span;
+use crate::error::ChiruError;
+use crate::violation::Violation;
+
+pub type PhaseId = usize;
+
+#[derive(Debug, Clone)]
+pub struct Phase {
+    pub id: PhaseId,
+    pub name: String,
+    pub order: usize,
+    /// True for the module-level phase used when a file declares none.
+    pub implicit: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// How a spawned context touches a value captured from outside it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadAccess {
+    Shared,
+    Exclusive,
+}
+
+/// A `spawn` block. Spawns run concurrently with every other spawn started
+/// in the same scope, so a record stays live until that scope exits.
+#[derive(Debug, Clone)]
+pub struct SpawnRecord {
+    pub scope: ScopeId,
+    pub parent: ScopeId,
+    pub accesses: Vec<(ValueId, ThreadAccess)>,
+    pub live: bool,
+}
+
+/// A high-to-low flow permitted inside `declassify`, with the assumptions
+/// of the enclosing unsafe block that justify it.
+#[derive(Debug, Clone)]
+pub struct Declassification {
+    pub from: ValueId,
+    pub to: ValueId,
+    pub assumptions: Vec<AssumptionId>,
+    pub span: Span,
+}
+
+/// The phase and what expires with it, saved before verifying a function
+/// body, whose phase changes only its calls make.
+pub struct SavedPhase {
+    phase: Option<PhaseId>,
+    lifetimes: Vec<(LifetimeId, bool)>,
+    capabilities: Vec<CapabilityNode>,
+    expired_grants: HashMap<ValueId, LifetimeId>,
+}
+
+/// What joining the two paths of an `if` found.
+pub struct Joined {
+    pub errors: Vec<ChiruError>,
+    /// Values the `else` path created and left in an enclosing scope, each
+    /// merged into the value of the same name and owner the `if` path left.
+    pub merged: HashMap<ValueId, ValueId>,
+    /// Values created and left in an enclosing scope on one path only.
+    /// They are given up at the join.
+    pub one_sided: HashSet<ValueId>,
+}
+
+/// Cloned to verify each branch of an `if` on its own copy.
+#[derive(Clone)]
+pub struct InterpreterState {
+    pub graph: ConstraintGraph,
+    pub scopes: HashMap<ScopeId, ScopeNode>,
+    pub scope_stack: Vec<ScopeId>,
+
+    next_value_id: ValueId,
+    next_region_id: RegionId,
+    next_lifetime_id: LifetimeId,
+    next_capability_id: CapabilityId,
+    next_scope_id: ScopeId,
+    next_assumption_id: AssumptionId,
+    pub current_phase: Option<PhaseId>,
+    pub phases: Vec<Phase>,
+    pub violations: Vec<Violation>,
+    pub spawns: Vec<SpawnRecord>,
+    pub declassifications: Vec<Declassification>,
+    /// Assumptions without `affects`, keyed to the unsafe scope whose
+    /// values they cover once it exits.
+    pending_affects: Vec<(AssumptionId, ScopeId)>,
+    /// The lifetime of the most recently expired capability on each value,
+    /// so a later access can say which lifetime ran out.
+    expired_grants: HashMap<ValueId, LifetimeId>,
+    /// Assumptions declared on a region with `region ... assume`; they
+    /// affect every value allocated in it.
+    region_assumptions: HashMap<RegionId, AssumptionId>,
+}
+
+impl InterpreterState {
+    pub fn new() -> Self {
+        InterpreterState {
+            graph: ConstraintGraph {
+                values: HashMap::new(),
+                regions: HashMap::new(),
+                lifetimes: HashMap::new(),
+                capabilities: HashMap::new(),
+                unsafe_assumptions: HashMap::new(),
+                ownership_edges: Vec::new(),
+            },
+            scopes: HashMap::new(),
+            scope_stack: Vec::new(),
+            next_value_id: 1,
+            next_region_id: 1,
+            next_lifetime_id: 1,
+            next_capability_id: 1,
+            next_scope_id: 1,
+            next_assumption_id: 1,
+            phases: Vec::new(),
+            current_phase: None,
+            violations: Vec::new(),
+            spawns: Vec::new(),
+            declassifications: Vec::new(),
+            pending_affects: Vec::new(),
+            expired_grants: HashMap::new(),
+            region_assumptions: HashMap::new(),
+        }
+    }
+
+    // =====================
+    // PHASE MANAGEMENT
+    // =====================
+
+    pub fn declare_phase(&mut self, name: String) -> Result<PhaseId, ChiruError> {
+        if self.phases.iter().any(|p| p.name == name) {
+            return Err(ChiruError::DuplicatePhase { phase: name });
+        }
+
+        let id = self.phases.len();
+        self.phases.push(Phase {
+            id,
+            name,
+            order: id,
+            implicit: false,
+        });
+
+        if self.current_phase.is_none() {
+            self.current_phase = Some(id);
+        }
+
+        Ok(id)
+    }
+
+    /// Enter the implicit module-level phase of a file without phase declarations.
+    pub fn declare_default_phase(&mut self) -> PhaseId {
+        let id = self.phases.len();
+        self.phases.push(Phase {
+            id,
+            name: "module".to_string(),
+            order: id,
+            implicit: true,
+        });
+        self.current_phase = Some(id);
+        id
+    }
+
+    pub fn current_phase(&self) -> PhaseId {
+        self.current_phase.expect("No active phase")
+    }
+
+    pub fn current_phase_name(&self) -> &str {
+        &self.phases[self.current_phase()].name
+    }
+
+    /// Move to the phase that directly follows the current one.
+    ///
+    /// Lifetimes and capabilities bound to the phase being left expire.
+    pub fn advance_phase(&mut self, name: &str) -> Result<PhaseId, ChiruError> {
+        let target = self.phases.iter().find(|p| p.name == name)
+            .ok_or_else(|| ChiruError::UnknownPhase { phase: name.to_string() })?;
+        let (target_id, target_order) = (target.id, target.order);
+
+        let current = &self.phases[self.current_phase()];
+
+        if target_order <= current.order {
+            return Err(ChiruError::PhaseRegression {
+                from: current.name.clone(),
+                to: name.to_string(),
+            });
+        }
+
+        if target_order > current.order + 1 {
+            let skipped = self.phases.iter()
+                .filter(|p| p.order > current.order && p.order < target_order)
+                .map(|p| p.name.clone())
+                .collect();
+            return Err(ChiruError::PhaseSkipped {
+                from: current.name.clone(),
+                to: name.to_string(),
+                skipped,
+            });
+        }
+
+        let previous = current.id;
+
+        for lifetime in self.graph.lifetimes.values_mut() {
+            if lifetime.phase == previous {
+                lifetime.active = false;
+            }
+        }
+
+        self.expire_capabilities(|cap| cap.phase == previous);
+        self.current_phase = Some(target_id);
+
+        Ok(target_id)
+    }
+
+    pub fn save_phase(&self) -> SavedPhase {
+        SavedPhase {
+            phase: self.current_phase,
+            lifetimes: self.graph.lifetimes.iter().map(|(id, l)| (*id, l.active)).collect(),
+            capabilities: self.graph.capabilities.values().cloned().collect(),
+            expired_grants: self.expired_grants.clone(),
+        }
+    }
+
+    /// Go back to the phase `saved` was taken in, undoing the expiry of the
+    /// lifetimes and capabilities that existed then.
+    pub fn restore_phase(&mut self, saved: SavedPhase) {
+        self.current_phase = saved.phase;
+        for (id, active) in saved.lifetimes {
+            self.graph.lifetimes.get_mut(&id).unwrap().active = active;
+        }
+        for cap in saved.capabilities {
+            self.graph.capabilities.entry(cap.id).or_insert(cap);
+        }
+        self.expired_grants = saved.expired_grants;
+    }
+
+    /// Start the body of the function in `scope`, which has not changed
+    /// phase yet, in the phase just before `target`: the phase its callers
+    /// must be in. What the body bound to the phase it started in moves
+    /// along.
+    pub fn rebase_phase(&mut self, scope: ScopeId, target: &str) {
+        let Some(target) = self.phases.iter().find(|p| p.name == target) else {
+            return;
+        };
+        let Some(entry) = self.phases.iter().find(|p| p.order + 1 == target.order) else {
+            return;
+        };
+        let (entry, started) = (entry.id, self.current_phase());
+        if entry == started {
+            return;
+        }
+
+        let scopes: HashSet<ScopeId> = self.scopes.keys()
+            .copied()
+            .filter(|id| self.is_within(*id, scope))
+            .collect();
+        for lifetime in self.graph.lifetimes.values_mut() {
+            if lifetime.phase == started && scopes.contains(&lifetime.scope) {
+                lifetime.phase = entry;
+            }
+        }
+        for cap in self.graph.capabilities.values_mut() {
+            if cap.phase == started && scopes.contains(&cap.scope) {
+                cap.phase = entry;
+            }
+        }
+        for assumption in self.graph.unsafe_assumptions.values_mut() {
+            if assumption.phase == started && scopes.contains(&assumption.scope) {
+                assumption.phase = entry;
+            }
+        }
+        self.current_phase = Some(entry);
+    }
+
+    // =====================
+    // SCOPE MANAGEMENT
+    // =====================
+
+    fn fresh_scope_id(&mut self) -> ScopeId {
+        let id = self.next_scope_id;
+        self.next_scope_id += 1;
+        id
+    }
+
+    pub fn current_scope(&self) -> ScopeId {
+        *self.scope_stack.last().expect("No active scope")
+    }
+
+    pub fn enter_scope(&mut self, kind: ScopeKind, name: Option<String>) -> ScopeId {
+        let id = self.fresh_scope_id();
+        let parent = self.scope_stack.last().copied();
+
+        let scope = ScopeNode {
+            parent,
+            kind,
+            name,
+            active: true,
+        };
+
+        self.scopes.insert(id, scope);
+        self.scope_stack.push(id);
+        id
+    }
+
+    /// Resolve a scope name against the enclosing scopes, innermost first.
+    pub fn resolve_scope(&self, lifetime: &str, name: &str) -> Result<ScopeId, ChiruError> {
+        if let Some(id) = self.find_enclosing_scope(name) {
+            return Ok(id);
+        }
+
+        if self.scopes.values().any(|s| s.name.as_deref() == Some(name)) {
+            Err(ChiruError::ScopeNotEnclosing {
+                lifetime: lifetime.to_string(),
+                scope: name.to_string(),
+            })
+        } else {
+            Err(ChiruError::UnknownScope {
+                lifetime: lifetime.to_string(),
+                scope: name.to_string(),
+            })
+        }
+    }
+
+    pub fn find_enclosing_scope(&self, name: &str) -> Option<ScopeId> {
+        self.scope_stack.iter().rev()
+            .find(|id| self.scopes[id].name.as_deref() == Some(name))
+            .copied()
+    }
+
+    pub fn is_module_scope(&self) -> bool {
+        self.scope_stack.len() == 1
+    }
+
+    /// Close the innermost scope, expiring what is bound to it and
+    /// destroying the values it still owns.
+    ///
+    /// Stack values are destroyed implicitly and static values survive.
+    /// Heap values must have been dropped or moved out, and external values
+    /// returned to their region; any still owned here are returned as errors.
+    pub fn exit_scope(&mut self) -> Result<Vec<ChiruError>, ChiruError> {
+        let scope_id = self.scope_stack.pop().ok_or(ChiruError::NoActiveScope)?;
+
+        // Assumptions without `affects` cover every value declared in this
+        // unsafe block, including values declared after the assumption
+        let (resolved, pending): (Vec<_>, Vec<_>) = self.pending_affects
+            .iter()
+            .partition(|(_, scope)| *scope == scope_id);
+        self.pending_affects = pending;
+
+        for (assumption, _) in resolved {
+            let mut affected: Vec<ValueId> = self.graph.values.values()
+                .filter(|v| self.is_within(v.scope, scope_id))
+                .map(|v| v.id)
+                .collect();
+            affected.sort();
+            self.graph.unsafe_assumptions.get_mut(&assumption).unwrap().affected_values = affected;
+        }
+
+        // Expire lifetimes in this scope
+        for lifetime in self.graph.lifetimes.values_mut() {
+            if lifetime.scope == scope_id {
+                lifetime.active = false;
+            }
+        }
+
+        // Expire capabilities in this scope
+        self.expire_capabilities(|cap| cap.scope == scope_id);
+
+        // Destroy owned values
+        let owned_values: Vec<ValueId> = self.graph.ownership_edges
+            .iter()
+            .filter(|edge| edge.owner == scope_id)
+            .map(|edge| edge.value)
+            .collect();
+
+        let mut errors: Vec<ChiruError> = owned_values.into_iter()
+            .filter_map(|value_id| self.abandon_value(value_id))
+            .collect();
+
+        // Regions declared here go away with the scope. A value still alive
+        // in one was moved out to an owner that outlives its memory.
+        let regions: Vec<RegionId> = self.graph.regions.values()
+            .filter(|r| r.scope == scope_id && !matches!(r.kind, RegionKind::Static))
+            .map(|r| r.id)
+            .collect();
+
+        for region_id in regions {
+            self.graph.regions.get_mut(&region_id).unwrap().active = false;
+
+            let mut dangling: Vec<ValueId> = self.graph.values.values()
+                .filter(|v| v.region == region_id && v.alive)
+                .map(|v| v.id)
+                .collect();
+            dangling.sort();
+
+            for value_id in dangling {
+                errors.push(ChiruError::ValueOutlivesRegion {
+                    value: self.graph.values[&value_id].name.clone(),
+                    region: self.graph.regions[&region_id].name.clone(),
+                });
+                self.graph.values.get_mut(&value_id).unwrap().alive = false;
+                self.graph.ownership_edges.retain(|e| e.value != value_id);
+            }
+        }
+
+        self.scopes.get_mut(&scope_id).unwrap().active = false;
+
+        // Spawns started here are joined when this scope ends
+        for spawn in self.spawns.iter_mut() {
+            if spawn.parent == scope_id {
+                spawn.live = false;
+            }
+        }
+
+        Ok(errors)
+    }
+
+    /// Give up `value` when its owner goes away without destroying it.
+    ///
+    /// Stack values die with their owner and static values survive it.
+    /// A heap value leaks and an external value is never returned, and a
+    /// secret must have been zeroized either way; the error is returned.
+    pub fn abandon_value(&mut self, value_id: ValueId) -> Option<ChiruError> {
+        self.graph.ownership_edges.retain(|e| e.value != value_id);
+
+        let value = &self.graph.values[&value_id];
+        let region = &self.graph.regions[&value.region];
+        let error = match region.kind {
+            RegionKind::Static => return None,
+            RegionKind::Stack => None,
+            RegionKind::Heap => Some(ChiruError::Leak {
+                value: value.name.clone(),
+                scope: self.scope_label(value.scope),
+            }),
+            RegionKind::External => Some(ChiruError::ExternalNotReturned {
+                value: value.name.clone(),
+                region: region.name.clone(),
+            }),
+        };
+
+        let value = self.graph.values.get_mut(&value_id).unwrap();
+        let error = match error {
+            Some(error) if value.alive => {
+                value.leaked = true;
+                Some(error)
+            }
+            _ if value.alive && value.secret && !value.zeroized => {
+                Some(ChiruError::SecretNotZeroized { value: value.name.clone() })
+            }
+            _ => None,
+        };
+        value.alive = false;
+        error
+    }
+
+    /// How a scope is named in diagnostics, e.g. "function `init`" or
+    /// "an unsafe block".
+    pub fn scope_label(&self, id: ScopeId) -> String {
+        let scope = &self.scopes[&id];
+        match (&scope.kind, &scope.name) {
+            (ScopeKind::Module, _) => "the module".to_string(),
+            (ScopeKind::Function, Some(name)) => format!("function `{}`", name),
+            (ScopeKind::Block, Some(name)) => format!("block `{}`", name),
+            (ScopeKind::Unsafe, _) => "an unsafe block".to_string(),
+            (ScopeKind::Spawn, _) => "a spawn block".to_string(),
+            (ScopeKind::Declassify, _) => "a declassify block".to_string(),
+            (_, _) => "a block".to_string(),
+        }
+    }
+
+    // =====================
+    // VALUE / REGION
+    // =====================
+
+    pub fn declare_region(
+        &mut self,
+        name: String,
+        kind: RegionKind,
+        label: Label,
+    ) -> Result<RegionId, ChiruError> {
+        let scope_id = self.current_scope();
+        let id = self.next_region_id;
+        self.next_region_id += 1;
+
+        self.graph.regions.insert(id, RegionNode {
+            id,
+            name,
+            kind,
+            scope: scope_id,
+            active: true,
+            label,
+        });
+
+        Ok(id)
+    }
+
+    /// Allocate a value in `region`, optionally derived from `sources`.
+    ///
+    /// A value is of unsafe origin when it is allocated in unsafe code, in a
+    /// region trusted under an assumption, or derived from an unsafe value.
+    pub fn allocate_value(
+        &mut self,
+        name: String,
+        region: RegionId,
+        sources: Vec<ValueId>,
+        secret: bool,
+    ) -> Result<ValueId, ChiruError> {
+        let region_node = &self.graph.regions[&region];
+        if !region_node.active {
+            return Err(ChiruError::InactiveRegion { region: region_node.name.clone() });
+        }
+
+        let id = self.next_value_id;
+        self.next_value_id += 1;
+
+        let assumption = self.region_assumptions.get(&region).copied();
+        if let Some(assumption) = assumption {
+            let node = self.graph.unsafe_assumptions.get_mut(&assumption).unwrap();
+            node.affected_values.push(id);
+        }
+
+        let tainted = sources.iter()
+            .any(|s| matches!(self.graph.values[s].origin, ValueOrigin::Unsafe));
+
+        self.graph.values.insert(id, ValueNode {
+            id,
+            name,
+            region,
+            scope: self.current_scope(),
+            alive: true,
+            leaked: false,
+            origin: if self.is_in_unsafe_scope() || assumption.is_some() || tainted {
+                ValueOrigin::Unsafe
+            } else {
+                ValueOrigin::Safe
+            },
+            derived_from: sources,
+            secret,
+            zeroized: false,
+            label: self.value_label(region, secret),
+            parameter: false,
+        });
+
+        self.graph.ownership_edges.push(OwnershipEdge {
+            value: id,
+            owner: self.current_scope(),
+        });
+
+        Ok(id)
+    }
+
+    // =====================
+    // LIFETIMES (PHASE-BOUND)
+    // =====================
+
+    pub fn create_lifetime(
+        &mut self,
+        name: String,
+        scope: ScopeId,
+        phase: PhaseId,
+    ) -> Result<LifetimeId, ChiruError> {
+        let id = self.next_lifetime_id;
+        self.next_lifetime_id += 1;
+
+        self.graph.lifetimes.insert(id, LifetimeNode {
+            name,
+            scope,
+            phase,
+            active: true,
+        });
+
+        Ok(id)
+    }
+
+    // =====================
+    // CAPABILITIES (PHASE-BOUND)
+    // =====================
+
+    /// Grant a capability on `value`, optionally reborrowed from `parent`.
+    ///
+    /// A reborrow must be on the same value, of a kind its parent includes,
+    /// and during a lifetime that ends no later than the parent's. It does
+    /// not conflict with the capabilities it was derived from; those are
+    /// suspended instead until it ends.
+    pub fn create_capability(
+        &mut self,
+        kind: CapabilityKind,
+        value: ValueId,
+        lifetime: LifetimeId,
+        phase: PhaseId,
+        parent: Option<CapabilityId>,
+    ) -> Result<CapabilityId, ChiruError> {
+
+        let value_node = &self.graph.values[&value];
+        if !value_node.alive {
+            return Err(ChiruError::CapabilityOnDestroyedValue {
+                value: value_node.name.clone(),
+            });
+        }
+
+        let lifetime_node = &self.graph.lifetimes[&lifetime];
+        if lifetime_node.phase != phase {
+            return Err(ChiruError::LifetimePhaseMismatch {
+                lifetime: lifetime_node.name.clone(),
+                created_in: self.phases[lifetime_node.phase].name.clone(),
+                used_in: self.current_phase_name().to_string(),
+            });
+        }
+
+        if !lifetime_node.active {
+            return Err(ChiruError::InactiveLifetime {
+                lifetime: lifetime_node.name.clone(),
+            });
+        }
+
+        let region = &self.graph.regions[&value_node.region];
+        let region_outlived = !matches!(region.kind, RegionKind::Static)
+            && !self.is_within(lifetime_node.scope, region.scope);
+        if region_outlived {
+            return Err(ChiruError::CapabilityOutlivesRegion {
+                value: value_node.name.clone(),
+                lifetime: lifetime_node.name.clone(),
+                region: region.name.clone(),
+            });
+        }
+
+        if let Some(parent_id) = parent {
+            let parent_cap = &self.graph.capabilities[&parent_id];
+            if parent_cap.value != value {
+                return Err(ChiruError::ReborrowOfOtherValue {
+                    value: value_node.name.clone(),
+                    parent_value: self.graph.values[&parent_cap.value].name.clone(),
+                });
+            }
+
+            if !parent_cap.kind.includes(kind) {
+                return Err(ChiruError::ReborrowExceedsParent {
+                    value: value_node.name.clone(),
+                    parent: format!("{:?}", parent_cap.kind),
+                    requested: format!("{:?}", kind),
+                });
+            }
+
+            let parent_lifetime = &self.graph.lifetimes[&parent_cap.lifetime];
+            if !self.is_within(lifetime_node.scope, parent_lifetime.scope) {
+                return Err(ChiruError::ReborrowOutlivesParent {
+                    value: value_node.name.clone(),
+                    lifetime: lifetime_node.name.clone(),
+                    parent_lifetime: parent_lifetime.name.clone(),
+                });
+            }
+        }
+
+        // Inside a spawn, the ThreadSend that handed the value over is the
+        // spawn's own authority, not a competing capability.
+        let authority = self.thread_authority(value);
+        let ancestors = self.capability_ancestors(parent);
+
+        for cap in self.graph.capabilities.values() {
+            if Some(cap.id) == authority || ancestors.contains(&cap.id) {
+                continue;
+            }
+            if cap.value == value && (cap.kind.is_exclusive() || kind.is_exclusive()) {
+                return Err(ChiruError::CapabilityConflict {
+                    value: value_node.name.clone(),
+                    held: format!("{:?}", cap.kind),
+                    requested: format!("{:?}", kind),
+                });
+            }
+        }
+
+        // A new write capability means the value may hold key material again
+        if kind.includes(CapabilityKind::UniqueMut) {
+            self.graph.values.get_mut(&value).unwrap().zeroized = false;
+        }
+
+        let id = self.next_capability_id;
+        self.next_capability_id += 1;
+
+        self.graph.capabilities.insert(id, CapabilityNode {
+            id,
+            kind,
+            value,
+            lifetime,
+            scope: self.current_scope(),
+            phase,
+            parent,
+        });
+
+        Ok(id)
+    }
+
+    /// `cap` and every capability it was reborrowed from, innermost first.
+    fn capability_ancestors(&self, cap: Option<CapabilityId>) -> Vec<CapabilityId> {
+        let mut chain = Vec::new();
+        let mut current = cap;
+        while let Some(id) = current {
+            chain.push(id);
+            current = self.graph.capabilities.get(&id).and_then(|c| c.parent);
+        }
+        chain
+    }
+
+    /// A capability is suspended while a capability reborrowed from it is live.
+    pub fn is_suspended(&self, cap: CapabilityId) -> bool {
+        self.graph.capabilities.values().any(|c| c.parent == Some(cap))
+    }
+
+    fn suspended_error(&self, cap: CapabilityId) -> ChiruError {
+        let node = &self.graph.capabilities[&cap];
+        ChiruError::CapabilitySuspended {
+            value: self.graph.values[&node.value].name.clone(),
+            kind: format!("{:?}", node.kind),
+        }
+    }
+
+    /// The most recently granted live capability of `kind` on `value`.
+    pub fn find_capability(&self, value: ValueId, kind: CapabilityKind) -> Option<CapabilityId> {
+        self.graph.capabilities.values()
+            .filter(|c| c.value == value && c.kind == kind)
+            .map(|c| c.id)
+            .max()
+    }
+
+    /// End a capability before its lifetime does.
+    ///
+    /// Unlike expiry, an explicit release is not remembered as an expired
+    /// grant: a later access reports a missing capability, not a lifetime.
+    /// Releasing a reborrow resumes its parent.
+    pub fn release_capability(&mut self, id: CapabilityId) -> Result<(), ChiruError> {
+        if self.is_suspended(id) {
+            return Err(self.suspended_error(id));
+        }
+
+        let cap = self.graph.capabilities.remove(&id).expect("Release of unknown capability");
+        if self.expired_grants.get(&cap.value) == Some(&cap.lifetime) {
+            self.expired_grants.remove(&cap.value);
+        }
+        Ok(())
+    }
+
+    /// Narrow a capability in place to a kind it includes, e.g. UniqueMut
+    /// to SharedRead once writing is done.
+    pub fn downgrade_capability(
+        &mut self,
+        id: CapabilityId,
+        kind: CapabilityKind,
+    ) -> Result<(), ChiruError> {
+        if self.is_suspended(id) {
+            return Err(self.suspended_error(id));
+        }
+
+        let cap = &self.graph.capabilities[&id];
+        if !cap.kind.includes(kind) {
+            return Err(ChiruError::InvalidDowngrade {
+                value: self.graph.values[&cap.value].name.clone(),
+                from: format!("{:?}", cap.kind),
+                to: format!("{:?}", kind),
+            });
+        }
+
+        self.graph.capabilities.get_mut(&id).unwrap().kind = kind;
+        Ok(())
+    }
+
+    // =====================
+    // ACCESS
+    // =====================
+
+    /// Check a read or write of `value` against the live graph.
+    ///
+    /// The value must be alive and covered by a capability whose lifetime
+    /// is still active and which is not suspended by a reborrow. Any
+    /// capability permits reading; writing requires one that includes
+    /// UniqueMut. ThreadSend hands the value to a spawn, so it only
+    /// authorizes access from inside one.
+    pub fn access_value(&self, value: ValueId, access: Access) -> Result<(), ChiruError> {
+        let node = &self.graph.values[&value];
+        let name = node.name.clone();
+
+        if !node.alive {
+            return Err(ChiruError::UseAfterFree { value: name });
+        }
+
+        let in_spawn = self.innermost_spawn().is_some();
+        let caps: Vec<&CapabilityNode> = self.graph.capabilities.values()
+            .filter(|c| c.value == value)
+            .filter(|c| c.kind != CapabilityKind::ThreadSend || in_spawn)
+            .collect();
+
+        let live: Vec<&CapabilityNode> = caps.iter()
+            .copied()
+            .filter(|c| self.graph.lifetimes[&c.lifetime].active)
+            .collect();
+
+        if live.is_empty() {
+            let expired = caps.first()
+                .map(|cap| cap.lifetime)
+                .or_else(|| self.expired_grants.get(&value).copied());
+
+            return Err(match expired {
+                Some(lifetime) => ChiruError::ExpiredLifetime {
+                    value: name,
+                    lifetime: self.graph.lifetimes[&lifetime].name.clone(),
+                },
+                None => ChiruError::MissingCapability {
+                    value: name,
+                    access: format!("{:?}", access).to_lowercase(),
+                },
+            });
+        }
+
+        let (suspended, usable): (Vec<&CapabilityNode>, Vec<&CapabilityNode>) = live.iter()
+            .copied()
+            .partition(|c| self.is_suspended(c.id));
+
+        if usable.is_empty() {
+            return Err(self.suspended_error(suspended[0].id));
+        }
+
+        let writes = |c: &CapabilityNode| c.kind.includes(CapabilityKind::UniqueMut);
+        if access == Access::Write && !usable.iter().any(|c| writes(c)) {
+            if let Some(cap) = suspended.iter().find(|c| writes(c)) {
+                return Err(self.suspended_error(cap.id));
+            }
+
+            let mut held: Vec<String> = usable.iter().map(|c| format!("{:?}", c.kind)).collect();
+            held.sort();
+            held.dedup();
+            return Err(ChiruError::WriteWithoutUniqueMut { value: name, held });
+        }
+
+        Ok(())
+    }
+
+    // =====================
+    // FUNCTIONS
+    // =====================
+
+    /// Bind the parameters of the function whose scope was just entered to
+    /// placeholder values, each covered for the whole body by the
+    /// capability its signature requires.
+    ///
+    /// The placeholders stand for the caller's memory: their region and
+    /// every borrowed parameter belong to the enclosing scope. An Own
+    /// parameter belongs to the function itself.
+    pub fn bind_parameters(
+        &mut self,
+        function: &str,
+        params: &[(String, CapabilityKind)],
+    ) -> Vec<ValueId> {
+        let scope_id = self.current_scope();
+        let caller = self.scopes[&scope_id].parent.expect("Function scope without a parent");
+        let phase = self.current_phase();
+
+        let region = self.next_region_id;
+        self.next_region_id += 1;
+        self.graph.regions.insert(region, RegionNode {
+            id: region,
+            name: format!("{}.params", function),
+            kind: RegionKind::Stack,
+            scope: caller,
+            active: true,
+            label: Label::Public,
+        });
+
+        let lifetime = self.create_lifetime(function.to_string(), scope_id, phase)
+            .expect("Lifetime creation cannot fail");
+
+        let mut ids = Vec::new();
+        for (name, kind) in params {
+            let id = self.allocate_value(name.clone(), region, Vec::new(), false)
+                .expect("Parameter region is active");
+            self.graph.values.get_mut(&id).unwrap().parameter = true;
+            if *kind != CapabilityKind::Own {
+                let edge = self.graph.ownership_edges.iter_mut()
+                    .find(|e| e.value == id)
+                    .unwrap();
+                edge.owner = caller;
+            }
+            self.create_capability(*kind, id, lifetime, phase, None)
+                .expect("Parameter capability on a fresh value");
+            ids.push(id);
+        }
+        ids
+    }
+
+    /// Check that the caller holds a usable capability on `value` that
+    /// includes what the parameter it is passed to requires.
+    pub fn check_call_argument(
+        &self,
+        function: &str,
+        value: ValueId,
+        required: CapabilityKind,
+    ) -> Result<(), ChiruError> {
+        let node = &self.graph.values[&value];
+        if !node.alive {
+            return Err(ChiruError::UseAfterFree { value: node.name.clone() });
+        }
+
+        let in_spawn = self.innermost_spawn().is_some();
+        let (suspended, usable): (Vec<&CapabilityNode>, Vec<&CapabilityNode>) = self.graph
+            .capabilities
+            .values()
+            .filter(|c| c.value == value && c.kind.includes(required))
+            .filter(|c| c.kind != CapabilityKind::ThreadSend || in_spawn)
+            .filter(|c| self.graph.lifetimes[&c.lifetime].active)
+            .partition(|c| self.is_suspended(c.id));
+
+        if !usable.is_empty() {
+            return Ok(());
+        }
+        if let Some(cap) = suspended.first() {
+            return Err(self.suspended_error(cap.id));
+        }
+        Err(ChiruError::MissingCallCapability {
+            function: function.to_string(),
+            value: node.name.clone(),
+            required: format!("{:?}", required),
+        })
+    }
+
+    /// Hand `value` to a callee that takes it by Own.
+    ///
+    /// The caller must own it and hold nothing on it but the Own
+    /// capability it passes along. The value itself stays where it is
+    /// until the callee's discharge of it is applied.
+    pub fn consume_argument(&mut self, value: ValueId) -> Result<(), ChiruError> {
+        let scope_id = self.acting_scope(value)?;
+        let name = self.graph.values[&value].name.clone();
+
+        if !self.graph.ownership_edges.iter().any(|e| e.value == value && e.owner == scope_id) {
+            return Err(ChiruError::MoveNotOwned { value: name });
+        }
+
+        if self.graph.capabilities.values()
+            .any(|c| c.value == value && c.kind != CapabilityKind::Own)
+        {
+            return Err(ChiruError::MoveWithActiveCapabilities { value: name });
+        }
+
+        self.graph.capabilities.retain(|_, c| c.value != value);
+        Ok(())
+    }
+
+    /// The scope that owns `value`, if any.
+    pub fn owner(&self, value: ValueId) -> Option<ScopeId> {
+        self.graph.ownership_edges.iter().find(|e| e.value == value).map(|e| e.owner)
+    }
+
+    /// The values allocated within `scope` that are still alive and owned
+    /// outside it: those moved out before it exited.
+    pub fn escaped_values(&self, scope: ScopeId) -> Vec<ValueId> {
+        let mut values: Vec<ValueId> = self.graph.values.values()
+            .filter(|v| v.alive && self.is_within(v.scope, scope))
+            .filter(|v| self.owner(v.id).is_some_and(|owner| !self.is_within(owner, scope)))
+            .map(|v| v.id)
+            .collect();
+        values.sort();
+        values
+    }
+
+    /// Take back a value a function body moved out. Only the function's
+    /// calls move values out; this one stands for what each of them does.
+    /// Returns the assumptions it rested on, for the values calls create.
+    pub fn retract_value(&mut self, value: ValueId) -> Vec<AssumptionId> {
+        self.graph.values.get_mut(&value).unwrap().alive = false;
+        self.graph.ownership_edges.retain(|e| e.value != value);
+
+        let mut assumptions = Vec::new();
+        for assumption in self.graph.unsafe_assumptions.values_mut() {
+            if assumption.affected_values.contains(&value) {
+                assumption.affected_values.retain(|v| *v != value);
+                assumptions.push(assumption.id);
+            }
+        }
+        assumptions.sort();
+        assumptions
+    }
+
+    /// Create the value a call moves out to `target`, like `template`, a
+    /// value retracted from the callee's body. It is allocated at the
+    /// call and rests on the assumptions the template rested on.
+    pub fn instantiate_value(
+        &mut self,
+        template: ValueId,
+        target: ScopeId,
+        assumptions: &[AssumptionId],
+    ) -> ValueId {
+        let id = self.next_value_id;
+        self.next_value_id += 1;
+
+        let mut node = self.graph.values[&template].clone();
+        node.id = id;
+        node.scope = self.current_scope();
+        node.alive = true;
+        node.leaked = false;
+        self.graph.values.insert(id, node);
+
+        for assumption in assumptions {
+            self.graph.unsafe_assumptions.get_mut(assumption).unwrap().affected_values.push(id);
+        }
+
+        self.graph.ownership_edges.push(OwnershipEdge { value: id, owner: target });
+        id
+    }
+
+    /// Drop an argument the callee dropped, wiped first if `zeroized`.
+    /// The callee has it either way; a failed drop is only reported.
+    pub fn drop_argument(&mut self, value: ValueId, zeroized: bool) -> Result<(), ChiruError> {
+        if zeroized {
+            self.graph.values.get_mut(&value).unwrap().zeroized = true;
+        }
+        let dropped = self.drop_value(value);
+        if dropped.is_err() {
+            self.graph.values.get_mut(&value).unwrap().alive = false;
+            self.graph.ownership_edges.retain(|e| e.value != value);
+        }
+        dropped
+    }
+
+    /// Give an argument the callee moved out to `target`, its new owner.
+    pub fn hand_over(&mut self, value: ValueId, target: ScopeId) {
+        for edge in self.graph.ownership_edges.iter_mut().filter(|e| e.value == value) {
+            edge.owner = target;
+        }
+    }
+
+    // =====================
+    // INFORMATION FLOW
+    // =====================
+
+    /// The label a value allocated in `region` gets: the region's, raised
+    /// to Secret for `let secret`.
+    pub fn value_label(&self, region: RegionId, secret: bool) -> Label {
+        let region_label = self.graph.regions[&region].label;
+        if secret { Label::Secret } else { region_label }
+    }
+
+    /// Check that `from` may flow into a value labelled `to_label`.
+    ///
+    /// A flow to a lower label is only allowed inside `declassify`, and only
+    /// if the enclosing unsafe block states at least one assumption. Those
+    /// assumptions are returned so the caller can record the point.
+    pub fn check_flow(
+        &self,
+        from: ValueId,
+        to: &str,
+        to_label: Label,
+    ) -> Result<Option<Vec<AssumptionId>>, ChiruError> {
+        let source = &self.graph.values[&from];
+        if source.label <= to_label {
+            return Ok(None);
+        }
+
+        if !self.is_in_declassify_scope() {
+            return Err(ChiruError::InformationFlow {
+                from: source.name.clone(),
+                to: to.to_string(),
+                from_label: format!("{:?}", source.label).to_lowercase(),
+                to_label: format!("{:?}", to_label).to_lowercase(),
+            });
+        }
+
+        let unsafe_scope = self.scope_stack.iter().rev()
+            .find(|id| matches!(self.scopes[id].kind, ScopeKind::Unsafe))
+            .copied()
+            .expect("declassify is only entered inside unsafe");
+
+        let mut assumptions: Vec<AssumptionId> = self.graph.unsafe_assumptions.values()
+            .filter(|ua| self.is_within(ua.scope, unsafe_scope))
+            .map(|ua| ua.id)
+            .collect();
+        assumptions.sort();
+
+        if assumptions.is_empty() {
+            return Err(ChiruError::DeclassifyWithoutAssumption {
+                from: source.name.clone(),
+                to: to.to_string(),
+            });
+        }
+
+        Ok(Some(assumptions))
+    }
+
+    pub fn record_declassification(
+        &mut self,
+        from: ValueId,
+        to: ValueId,
+        assumptions: Vec<AssumptionId>,
+        span: Span,
+    ) {
+        self.declassifications.push(Declassification { from, to, assumptions, span });
+    }
+
+    /// Copy the contents of `from` into `into`: a read of one and a write
+    /// of the other. `into` now depends on `from` like a derived value.
+    pub fn copy_value(&mut self, from: ValueId, into: ValueId) -> Result<(), ChiruError> {
+        self.access_value(from, Access::Read)?;
+        self.write_value(into)?;
+        self.flow_into(from, into);
+        Ok(())
+    }
+
+    /// Make `into` depend on `from` like a value derived from it, tainted
+    /// if `from` is.
+    pub fn flow_into(&mut self, from: ValueId, into: ValueId) {
+        let tainted = matches!(self.graph.values[&from].origin, ValueOrigin::Unsafe);
+        let target = self.graph.values.get_mut(&into).unwrap();
+        if !target.derived_from.contains(&from) && from != into {
+            target.derived_from.push(from);
+        }
+        if tainted {
+            target.origin = ValueOrigin::Unsafe;
+        }
+    }
+
+    /// `value` and every value it was derived from, directly or through
+    /// others.
+    pub fn lineage(&self, value: ValueId) -> Vec<ValueId> {
+        let mut lineage = vec![value];
+        let mut i = 0;
+        while i < lineage.len() {
+            for source in &self.graph.values[&lineage[i]].derived_from {
+                if !lineage.contains(source) {
+                    lineage.push(*source);
+                }
+            }
+            i += 1;
+        }
+        lineage
+    }
+
+    pub fn write_value(&mut self, value: ValueId) -> Result<(), ChiruError> {
+        self.access_value(value, Access::Write)?;
+        self.graph.values.get_mut(&value).unwrap().zeroized = false;
+        Ok(())
+    }
+
+    /// Apply a write a callee made to `value` through its parameter. The
+    /// call was checked against the caller's capabilities already.
+    pub fn clobber_value(&mut self, value: ValueId) {
+        self.graph.values.get_mut(&value).unwrap().zeroized = false;
+    }
+
+    /// Wipe a value's contents. Zeroizing is a write, so it needs a
+    /// capability that includes UniqueMut.
+    pub fn zeroize_value(&mut self, value: ValueId) -> Result<(), ChiruError> {
+        self.access_value(value, Access::Write)?;
+        self.graph.values.get_mut(&value).unwrap().zeroized = true;
+        Ok(())
+    }
+
+    // =====================
+    // THREADS
+    // =====================
+
+    pub fn begin_spawn(&mut self, scope: ScopeId) {
+        let parent = self.scopes[&scope].parent.expect("Spawn outside any scope");
+        self.spawns.push(SpawnRecord {
+            scope,
+            parent,
+            accesses: Vec::new(),
+            live: true,
+        });
+    }
+
+    /// Position of the innermost spawn scope in the scope stack.
+    fn innermost_spawn(&self) -> Option<usize> {
+        self.scope_stack.iter().rposition(|id| {
+            matches!(self.scopes[id].kind, ScopeKind::Spawn)
+        })
+    }
+
+    /// True if `value` is owned outside the innermost spawn, i.e. the spawn
+    /// reaches it across a thread boundary.
+    fn is_captured(&self, value: ValueId) -> bool {
+        let Some(spawn_pos) = self.innermost_spawn() else {
+            return false;
+        };
+
+        match self.graph.ownership_edges.iter().find(|e| e.value == value) {
+            Some(edge) => self.scope_stack[..spawn_pos].contains(&edge.owner),
+            None => false,
+        }
+    }
+
+    /// The ThreadSend capability that hands a captured value to the
+    /// innermost spawn, if any.
+    fn thread_authority(&self, value: ValueId) -> Option<CapabilityId> {
+        if !self.is_captured(value) {
+            return None;
+        }
+
+        self.graph.capabilities.values()
+            .find(|c| c.value == value && c.kind == CapabilityKind::ThreadSend)
+            .map(|c| c.id)
+    }
+
+    /// Check a use of `value` from inside a spawn.
+    ///
+    /// A captured value must be handed over with ThreadSend (moved, exclusive)
+    /// or ThreadShare (shared). Exclusive access from one spawn conflicts with
+    /// any access from another concurrently live spawn: that is a data race.
+    pub fn check_thread_access(
+        &mut self,
+        value: ValueId,
+        mutable: bool,
+    ) -> Result<(), ChiruError> {
+        if !self.is_captured(value) {
+            return Ok(());
+        }
+
+        let name = self.graph.values[&value].name.clone();
+
+        let thread_cap = self.graph.capabilities.values()
+            .filter(|c| c.value == value)
+            .map(|c| c.kind)
+            .find(|k| matches!(k, CapabilityKind::ThreadSend | CapabilityKind::ThreadShare))
+            .ok_or_else(|| ChiruError::MissingThreadCapability { value: name.clone() })?;
+
+        let access = if mutable || thread_cap == CapabilityKind::ThreadSend {
+            ThreadAccess::Exclusive
+        } else {
+            ThreadAccess::Shared
+        };
+
+        let spawn_scope = self.scope_stack[self.innermost_spawn().unwrap()];
+        let enclosing: Vec<ScopeId> = self.scope_stack.clone();
+
+        let racing = self.spawns.iter().any(|s| {
+            s.live
+                && s.scope != spawn_scope
+                && !enclosing.contains(&s.scope)
+                && s.accesses.iter().any(|(v, a)| {
+                    *v == value
+                        && (*a == ThreadAccess::Exclusive || access == ThreadAccess::Exclusive)
+                })
+        });
+
+        if racing {
+            return Err(ChiruError::DataRace { value: name });
+        }
+
+        let spawn = self.spawns.iter_mut().rev().find(|s| s.scope == spawn_scope).unwrap();
+        spawn.accesses.push((value, access));
+
+        Ok(())
+    }
+
+    /// Remove every capability matching `expired`, remembering its lifetime.
+    fn expire_capabilities(&mut self, expired: impl Fn(&CapabilityNode) -> bool) {
+        let ids: Vec<CapabilityId> = self.graph.capabilities.values()
+            .filter(|cap| expired(cap))
+            .map(|cap| cap.id)
+            .collect();
+
+        for id in ids {
+            let cap = self.graph.capabilities.remove(&id).unwrap();
+            self.expired_grants.insert(cap.value, cap.lifetime);
+        }
+    }
+
+    // =====================
+    // UNSAFE ASSUMPTIONS (PHASE-BOUND)
+    // =====================
+
+    pub fn add_unsafe_assumption(
+        &mut self,
+        description: String,
+        phase: PhaseId,
+        affected_values: Option<Vec<ValueId>>,
+        span: Span,
+    ) -> Result<AssumptionId, ChiruError> {
+
+        let unsafe_scope = self.scope_stack.iter().rev()
+            .find(|id| matches!(self.scopes[id].kind, ScopeKind::Unsafe))
+            .copied()
+            .ok_or(ChiruError::AssumptionOutsideUnsafe)?;
+
+        let id = self.next_assumption_id;
+        self.next_assumption_id += 1;
+
+        let inferred = affected_values.is_none();
+        if inferred {
+            self.pending_affects.push((id, unsafe_scope));
+        }
+
+        self.graph.unsafe_assumptions.insert(id, UnsafeAssumptionNode {
+            id,
+            description,
+            scope: self.current_scope(),
+            phase,
+            affected_values: affected_values.unwrap_or_default(),
+            inferred,
+            span,
+        });
+
+        Ok(id)
+    }
+
+    /// Every assumption `value` rests on, directly or through the values it
+    /// was derived from, with the derivation path to the affected value.
+    ///
+    /// The path is empty when the assumption affects `value` itself;
+    /// otherwise it runs from the immediate source to the affected value.
+    pub fn assumption_chain(&self, value: ValueId) -> Vec<(AssumptionId, Vec<ValueId>)> {
+        let mut chain: Vec<(AssumptionId, Vec<ValueId>)> = Vec::new();
+        let mut visited = vec![value];
+        let mut queue = VecDeque::from([(value, Vec::new())]);
+
+        while let Some((current, path)) = queue.pop_front() {
+            let mut affecting: Vec<AssumptionId> = self.graph.unsafe_assumptions.values()
+                .filter(|ua| ua.affected_values.contains(&current))
+                .map(|ua| ua.id)
+                .collect();
+            affecting.sort();
+
+            for id in affecting {
+                if !chain.iter().any(|(seen, _)| *seen == id) {
+                    chain.push((id, path.clone()));
+                }
+            }
+
+            for source in &self.graph.values[&current].derived_from {
+                if !visited.contains(source) {
+                    visited.push(*source);
+                    let mut next = path.clone();
+                    next.push(*source);
+                    queue.push_back((*source, next));
+                }
+            }
+        }
+
+        chain.sort_by_key(|(id, _)| *id);
+        chain
+    }
+
+    /// Record the assumption an external region is declared with outside
+    /// unsafe code. Values later allocated in the region are affected by it.
+    pub fn add_region_assumption(
+        &mut self,
+        description: String,
+        phase: PhaseId,
+        region: RegionId,
+        span: Span,
+    ) -> AssumptionId {
+        let id = self.next_assumption_id;
+        self.next_assumption_id += 1;
+
+        self.graph.unsafe_assumptions.insert(id, UnsafeAssumptionNode {
+            id,
+            description,
+            scope: self.current_scope(),
+            phase,
+            affected_values: Vec::new(),
+            inferred: false,
+            span,
+        });
+        self.region_assumptions.insert(region, id);
+
+        id
+    }
+
+    /// Forget `repeated`, stated again by a later iteration of a loop, for
+    /// `earlier`, stated by the same statement before. What it affects,
+    /// `earlier` affects.
+    pub fn merge_assumption(&mut self, repeated: AssumptionId, earlier: AssumptionId) {
+        let node = self.graph.unsafe_assumptions.remove(&repeated).unwrap();
+        let target = self.graph.unsafe_assumptions.get_mut(&earlier).unwrap();
+        for value in node.affected_values {
+            if !target.affected_values.contains(&value) {
+                target.affected_values.push(value);
+            }
+        }
+
+        for (id, _) in self.pending_affects.iter_mut() {
+            if *id == repeated {
+                *id = earlier;
+            }
+        }
+        for id in self.region_assumptions.values_mut() {
+            if *id == repeated {
+                *id = earlier;
+            }
+        }
+        for declassification in self.declassifications.iter_mut() {
+            let assumptions = &mut declassification.assumptions;
+            if let Some(i) = assumptions.iter().position(|id| *id == repeated) {
+                assumptions.remove(i);
+                if !assumptions.contains(&earlier) {
+                    assumptions.push(earlier);
+                    assumptions.sort();
+                }
+            }
+        }
+
+        // Keep the numbering dense when the latest assumption goes
+        if repeated + 1 == self.next_assumption_id {
+            self.next_assumption_id = repeated;
+        }
+    }
+
+    // =====================
+    // BRANCHES
+    // =====================
+
+    /// Continue id allocation where `other` left off, so nodes created on
+    /// two paths forked from the same state never share an id.
+    pub fn continue_ids_from(&mut self, other: &InterpreterState) {
+        self.next_value_id = other.next_value_id;
+        self.next_region_id = other.next_region_id;
+        self.next_lifetime_id = other.next_lifetime_id;
+        self.next_capability_id = other.next_capability_id;
+        self.next_scope_id = other.next_scope_id;
+        self.next_assumption_id = other.next_assumption_id;
+    }
+
+    /// Join the states the two paths of an `if` ended in. Both were forked
+    /// from `self`, the `else` path continuing the ids of the `if` path.
+    ///
+    /// A value is alive afterwards only if it is alive, with the same owner,
+    /// on both paths, and a capability only held if held on both. A value
+    /// dropped or moved on one path but kept on the other is returned as an
+    /// inconsistency. Values both paths create and leave with the same name
+    /// and owner become one; one created on a single path is inconsistent.
+    pub fn join(
+        &mut self,
+        then_state: InterpreterState,
+        else_state: InterpreterState,
+    ) -> Joined {
+        let base = std::mem::replace(self, then_state);
+        let mut errors = Vec::new();
+        let owner = |state: &InterpreterState, value: ValueId| {
+            state.graph.ownership_edges.iter().find(|e| e.value == value).map(|e| e.owner)
+        };
+
+        // Values created on a path that outlive it, paired up by name and
+        // owner; the `else` ids continue the `if` ids, so each side is in
+        // creation order
+        let created = |state: &InterpreterState| {
+            let mut ids: Vec<ValueId> = state.graph.values.values()
+                .filter(|v| v.alive && !base.graph.values.contains_key(&v.id))
+                .map(|v| v.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        let mut then_created = created(self);
+        let mut merged = HashMap::new();
+        let mut one_sided = HashSet::new();
+        for id in created(&else_state) {
+            let name = &else_state.graph.values[&id].name;
+            let twin = then_created.iter().position(|t| {
+                self.graph.values[t].name == *name && owner(self, *t) == owner(&else_state, id)
+            });
+            match twin {
+                Some(i) => {
+                    merged.insert(id, then_created.remove(i));
+                }
+                None => {
+                    one_sided.insert(id);
+                }
+            }
+        }
+        one_sided.extend(then_created);
+        let mut one_sided_ids: Vec<ValueId> = one_sided.iter().copied().collect();
+        one_sided_ids.sort();
+        for id in one_sided_ids {
+            let path = match self.graph.values.contains_key(&id) {
+                true => "`if`",
+                false => "`else`",
+            };
+            let name = match self.graph.values.get(&id) {
+                Some(node) => node.name.clone(),
+                None => else_state.graph.values[&id].name.clone(),
+            };
+            errors.push(ChiruError::BranchInconsistency {
+                value: name,
+                action: "created".to_string(),
+                path: path.to_string(),
+            });
+        }
+        let same = |id: &ValueId| merged.get(id).copied().unwrap_or(*id);
+
+        // A capability released on one path only would be taken as held,
+        // or as released, by the code after the `if` whichever path ran.
+        // A value given up on one path only is reported below instead
+        let mut released: Vec<&CapabilityNode> = base.graph.capabilities.values()
+            .filter(|c| {
+                self.graph.capabilities.contains_key(&c.id)
+                    != else_state.graph.capabilities.contains_key(&c.id)
+            })
+            .filter(|c| {
+                self.graph.values[&c.value].alive && else_state.graph.values[&c.value].alive
+            })
+            .collect();
+        released.sort_by_key(|c| c.id);
+        for cap in released {
+            let path = match self.graph.capabilities.contains_key(&cap.id) {
+                true => "`else`",
+                false => "`if`",
+            };
+            errors.push(ChiruError::BranchReleaseMismatch {
+                kind: format!("{:?}", cap.kind),
+                value: self.graph.values[&cap.value].name.clone(),
+                path: path.to_string(),
+            });
+        }
+
+        let mut ids: Vec<ValueId> = else_state.graph.values.keys().copied().collect();
+        ids.sort();
+        for id in ids {
+            let other = &else_state.graph.values[&id];
+            if let Some(twin) = merged.get(&id) {
+                let node = self.graph.values.get_mut(twin).unwrap();
+                node.leaked |= other.leaked;
+                node.zeroized &= other.zeroized;
+                node.label = node.label.max(other.label);
+                if matches!(other.origin, ValueOrigin::Unsafe) {
+                    node.origin = ValueOrigin::Unsafe;
+                }
+                for source in other.derived_from.iter().map(same) {
+                    if !node.derived_from.contains(&source) {
+                        node.derived_from.push(source);
+                    }
+                }
+                continue;
+            }
+            if !base.graph.values.contains_key(&id) {
+                let mut node = other.clone();
+                node.derived_from = node.derived_from.iter().map(same).collect();
+                self.graph.values.insert(id, node);
+                continue;
+            }
+
+            // Kept: still owned where it was before the `if`
+            let before = owner(&base, id);
+            let then_owner = owner(self, id);
+            let else_owner = owner(&else_state, id);
+            let node = self.graph.values.get_mut(&id).unwrap();
+            let then_kept = node.alive && then_owner == before;
+            let else_kept = other.alive && else_owner == before;
+            let agreed = node.alive && other.alive && then_owner == else_owner;
+
+            node.leaked |= other.leaked;
+            node.zeroized &= other.zeroized;
+            if matches!(other.origin, ValueOrigin::Unsafe) {
+                node.origin = ValueOrigin::Unsafe;
+            }
+            for source in &other.derived_from {
+                if !node.derived_from.contains(source) {
+                    node.derived_from.push(*source);
+                }
+            }
+
+            if then_kept != else_kept {
+                let path = if then_kept { "`else`" } else { "`if`" };
+                errors.push(ChiruError::BranchInconsistency {
+                    value: node.name.clone(),
+                    action: "dropped or moved".to_string(),
+                    path: path.to_string(),
+                });
+                node.alive = false;
+                self.graph.ownership_edges.retain(|e| e.value != id);
+            } else if !agreed && (node.alive || other.alive) {
+                // Given up on both paths, but handed to another scope on one:
+                // that scope still owes it, as it would on that path alone
+                let (path, owner) = match node.alive {
+                    true => ("`if`", then_owner),
+                    false => ("`else`", else_owner),
+                };
+                let owner = owner.expect("Live value has an owner");
+                let value = node.name.clone();
+                if !node.alive {
+                    node.alive = true;
+                    self.graph.ownership_edges.push(OwnershipEdge { value: id, owner });
+                }
+                errors.push(ChiruError::BranchOwnerMismatch {
+                    value,
+                    owner: self.scope_label(owner),
+                    path: path.to_string(),
+                });
+            } else if !agreed {
+                node.alive = false;
+                self.graph.ownership_edges.retain(|e| e.value != id);
+            }
+        }
+
+        for edge in &else_state.graph.ownership_edges {
+            let created = !base.graph.values.contains_key(&edge.value);
+            if created && !merged.contains_key(&edge.value) {
+                self.graph.ownership_edges.push(OwnershipEdge {
+                    value: edge.value,
+                    owner: edge.owner,
+                });
+            }
+        }
+        for id in &one_sided {
+            self.graph.values.get_mut(id).unwrap().alive = false;
+        }
+        self.graph.ownership_edges.retain(|e| !one_sided.contains(&e.value));
+
+        self.graph.capabilities.retain(|id, _| else_state.graph.capabilities.contains_key(id));
+
+        for (id, region) in &else_state.graph.regions {
+            self.graph.regions.entry(*id).or_insert_with(|| region.clone());
+        }
+        for (id, lifetime) in &else_state.graph.lifetimes {
+            self.graph.lifetimes.entry(*id).or_insert_with(|| lifetime.clone());
+        }
+        for (id, assumption) in &else_state.graph.unsafe_assumptions {
+            let node = self.graph.unsafe_assumptions.entry(*id)
+                .or_insert_with(|| UnsafeAssumptionNode {
+                    affected_values: Vec::new(),
+                    ..assumption.clone()
+                });
+            for value in assumption.affected_values.iter().map(same) {
+                if !node.affected_values.contains(&value) {
+                    node.affected_values.push(value);
+                }
+            }
+        }
+        for (id, scope) in &else_state.scopes {
+            self.scopes.entry(*id).or_insert_with(|| scope.clone());
+        }
+
+        for (i, spawn) in else_state.spawns.iter().enumerate() {
+            if i >= base.spawns.len() {
+                self.spawns.push(spawn.clone());
+                continue;
+            }
+            for (value, access) in &spawn.accesses {
+                let access = (same(value), *access);
+                if !self.spawns[i].accesses.contains(&access) {
+                    self.spawns[i].accesses.push(access);
+                }
+            }
+        }
+
+        for pending in &else_state.pending_affects {
+            if !self.pending_affects.contains(pending) {
+                self.pending_affects.push(*pending);
+            }
+        }
+        for (value, lifetime) in &else_state.expired_grants {
+            self.expired_grants.entry(same(value)).or_insert(*lifetime);
+        }
+        for (region, assumption) in &else_state.region_assumptions {
+            self.region_assumptions.entry(*region).or_insert(*assumption);
+        }
+
+        self.violations.extend(else_state.violations[base.violations.len()..].iter().cloned());
+        self.declassifications.extend(
+            else_state.declassifications[base.declassifications.len()..].iter().map(|d| {
+                Declassification { from: same(&d.from), to: same(&d.to), ..d.clone() }
+            }),
+        );
+        self.continue_ids_from(&else_state);
+
+        if self.current_phase != else_state.current_phase {
+            errors.push(ChiruError::BranchPhaseMismatch {
+                then_phase: self.current_phase_name().to_string(),
+                else_phase: else_state.current_phase_name().to_string(),
+            });
+        }
+
+        Joined { errors, merged, one_sided }
+    }
+
+    // =====================
+    // LOOPS
+    // =====================
+
+    /// The values allocated since the graph held those in `before` that
+    /// are still alive or were leaked, by name.
+    pub fn loop_residue(&self, before: &HashSet<ValueId>) -> Vec<String> {
+        let mut values: Vec<String> = self.graph.values.values()
+            .filter(|v| (v.alive || v.leaked) && !before.contains(&v.id))
+            .map(|v| format!("`{}`", v.name))
+            .collect();
+        values.sort();
+        values
+    }
+
+    /// What a loop iteration leaves for the next one to start from: each
+    /// value in `before`, by id, and each value allocated since that is
+    /// still alive or was leaked, by name, with its state and owner, and
+    /// every capability still held. Grants made in an iteration expire
+    /// with its block, but it may release or reborrow older ones.
+    pub fn loop_state(&self, before: &HashSet<ValueId>) -> Vec<String> {
+        let key = |id: &ValueId| match before.contains(id) {
+            true => format!("#{}", id),
+            false => format!("`{}`", self.graph.values[id].name),
+        };
+
+        let mut facts: Vec<String> = self.graph.values.values()
+            .filter(|v| before.contains(&v.id) || v.alive || v.leaked)
+            .map(|v| {
+                format!(
+                    "value {} alive={} leaked={} zeroized={} owner={:?}",
+                    key(&v.id), v.alive, v.leaked, v.zeroized, self.owner(v.id),
+                )
+            })
+            .collect();
+        facts.extend(self.graph.capabilities.values().map(|c| {
+            format!(
+                "capability {:?} on {} during {} reborrowed={}",
+                c.kind, key(&c.value), c.lifetime, c.parent.is_some(),
+            )
+        }));
+        facts.sort();
+        facts
+    }
+
+    // =====================
+    // VIOLATIONS
+    // =====================
+
+    pub fn record_violation(&mut self, error: ChiruError, statement: String, span: Span) {
+        self.violations.push(Violation::new(error, statement, span));
+    }
+
+    // =====================
+    // HELPERS
+    // =====================
+
+    /// True if `scope` is `ancestor` or nested inside it.
+    pub fn is_within(&self, scope: ScopeId, ancestor: ScopeId) -> bool {
+        let mut current = Some(scope);
+        while let Some(id) = current {
+            if id == ancestor {
+                return true;
+            }
+            current = self.scopes[&id].parent;
+        }
+        false
+    }
+
+    /// The scope on the current stack at the depth of `scope`: `scope`
+    /// itself while it is open, or, for a scope a loop iteration opened,
+    /// the one the current iteration opened in its place.
+    pub fn counterpart(&self, scope: ScopeId) -> ScopeId {
+        let mut depth = 0;
+        let mut current = self.scopes[&scope].parent;
+        while let Some(id) = current {
+            depth += 1;
+            current = self.scopes[&id].parent;
+        }
+        self.scope_stack[depth]
+    }
+
+    pub fn is_in_unsafe_scope(&self) -> bool {
+        self.scope_stack.iter().any(|id| {
+            matches!(self.scopes[id].kind, ScopeKind::Unsafe)
+        })
+    }
+
+    pub fn is_in_declassify_scope(&self) -> bool {
+        self.scope_stack.iter().any(|id| {
+            matches!(self.scopes[id].kind, ScopeKind::Declassify)
+        })
+    }
+
+    /// The scope that drops, moves or returns `value` from the current
+    /// scope: its owner if that is the current scope or is reached from it
+    /// through plain blocks only, such as the paths of an `if`; otherwise
+    /// the current scope, which then fails the ownership check.
+    fn acting_scope(&self, value: ValueId) -> Result<ScopeId, ChiruError> {
+        let current = *self.scope_stack.last().ok_or(ChiruError::NoActiveScope)?;
+        for id in self.scope_stack.iter().rev() {
+            if self.graph.ownership_edges.iter().any(|e| e.value == value && e.owner == *id) {
+                return Ok(*id);
+            }
+            if !matches!(self.scopes[id].kind, ScopeKind::Block) {
+                break;
+            }
+        }
+        Ok(current)
+    }
+
+    /// Transfer ownership of `value` from the current scope to `target`.
+    ///
+    /// The value then lives until `target` exits instead of the current
+    /// scope. It cannot be moved while capabilities on it are live.
+    pub fn move_value(&mut self, value: ValueId, target: ScopeId) -> Result<(), ChiruError> {
+        let scope_id = self.acting_scope(value)?;
+        let name = self.graph.values[&value].name.clone();
+
+        if !self.graph.values[&value].alive {
+            return Err(ChiruError::UseAfterFree { value: name });
+        }
+
+        let edge = self.graph.ownership_edges.iter_mut()
+            .find(|e| e.value == value && e.owner == scope_id)
+            .ok_or_else(|| ChiruError::MoveNotOwned { value: name.clone() })?;
+
+        if self.graph.capabilities.values().any(|cap| cap.value == value) {
+            return Err(ChiruError::MoveWithActiveCapabilities { value: name });
+        }
+
+        edge.owner = target;
+        Ok(())
+    }
+
+    pub fn drop_value(&mut self, value: ValueId) -> Result<(), ChiruError> {
+        let scope_id = self.acting_scope(value)?;
+
+        let name = self.graph.values[&value].name.clone();
+
+        if !self.graph.values[&value].alive {
+            return Err(ChiruError::DoubleDrop { value: name });
+        }
+
+        // External memory goes back to its provider; static memory never goes
+        let region = &self.graph.regions[&self.graph.values[&value].region];
+        match region.kind {
+            RegionKind::External => {
+                return Err(ChiruError::ExternalDrop {
+                value: name,
+                region: region.name.clone(),
+            });
+            }
+            RegionKind::Static => return Err(ChiruError::StaticDrop { value: name }),
+            RegionKind::Stack | RegionKind::Heap => {}
+        }
+
+        self.destroy_owned(scope_id, value)
+    }
+
+    /// Hand an external value back to the region that provided it.
+    ///
+    /// This is the only way external memory is released: the same
+    /// ownership and capability rules as `drop` apply.
+    pub fn return_value(&mut self, value: ValueId, region: RegionId) -> Result<(), ChiruError> {
+        let scope_id = self.acting_scope(value)?;
+        let node = &self.graph.values[&value];
+        let name = node.name.clone();
+
+        if !node.alive {
+            return Err(ChiruError::DoubleDrop { value: name });
+        }
+
+        let target = &self.graph.regions[&region];
+        if node.region != region {
+            return Err(ChiruError::ReturnToWrongRegion {
+                value: name,
+                region: target.name.clone(),
+                allocated_in: self.graph.regions[&node.region].name.clone(),
+            });
+        }
+
+        if !matches!(target.kind, RegionKind::External) {
+            return Err(ChiruError::ReturnNotExternal {
+                value: name,
+                region: target.name.clone(),
+            });
+        }
+
+        self.destroy_owned(scope_id, value)
+    }
+
+    /// Destroy `value` on behalf of `scope_id`, which must own it, once no
+    /// capability on it is live.
+    fn destroy_owned(&mut self, scope_id: ScopeId, value: ValueId) -> Result<(), ChiruError> {
+        let name = self.graph.values[&value].name.clone();
+
+        // Ownership check
+        if !self.graph.ownership_edges.iter().any(|e| {
+            e.value == value && e.owner == scope_id
+        }) {
+            return Err(ChiruError::DropNotOwned {
+                value: name,
+                scope: self.scope_label(scope_id),
+            });
+        }
+
+        // Capability check (regardless of phase)
+        let has_active_caps = self.graph.capabilities.values().any(|cap| {
+            cap.value == value
+        });
+
+        if has_active_caps {
+            return Err(ChiruError::DropWithActiveCapabilities { value: name });
+        }
+
+        let node = &self.graph.values[&value];
+        if node.secret && !node.zeroized {
+            return Err(ChiruError::SecretNotZeroized { value: name });
+        }
+
+        // Destroy value
+        self.graph.values.get_mut(&value).unwrap().alive = false;
+
+        // Remove ownership edges
+        self.graph.ownership_edges.retain(|edge| edge.value != value);
+
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn function_with_heap() -> (InterpreterState, RegionId) {
+        let mut state = InterpreterState::new();
+        state.declare_default_phase();
+        state.enter_scope(ScopeKind::Function, Some("f".to_string()));
+        let region = state
+            .declare_region("h".to_string(), RegionKind::Heap, Label::Public)
+            .unwrap();
+        (state, region)
+    }
+
+    #[test]
+    fn drop_requires_no_live_capability() {
+        let (mut state, region) = function_with_heap();
+        let value = state.allocate_value("v".to_string(), region, Vec::new(), false).unwrap();
+        let scope = state.current_scope();
+        let lifetime = state.create_lifetime("l".to_string(), scope, 0).unwrap();
+        let cap = state
+            .create_capability(CapabilityKind::UniqueMut, value, lifetime, 0, None)
+            .unwrap();
+
+        assert!(matches!(
+            state.drop_value(value),
+            Err(ChiruError::DropWithActiveCapabilities { .. })
+        ));
+        state.release_capability(cap).unwrap();
+        assert!(state.drop_value(value).is_ok());
+    }
+
+    #[test]
+    fn downgrade_only_goes_down_the_lattice() {
+        let (mut state, region) = function_with_heap();
+        let value = state.allocate_value("v".to_string(), region, Vec::new(), false).unwrap();
+        let scope = state.current_scope();
+        let lifetime = state.create_lifetime("l".to_string(), scope, 0).unwrap();
+        let cap = state
+            .create_capability(CapabilityKind::UniqueMut, value, lifetime, 0, None)
+            .unwrap();
+
+        state.downgrade_capability(cap, CapabilityKind::SharedRead).unwrap();
+        assert!(matches!(
+            state.downgrade_capability(cap, CapabilityKind::UniqueMut),
+            Err(ChiruError::InvalidDowngrade { .. })
+        ));
+        assert!(matches!(
+            state.access_value(value, Access::Write),
+            Err(ChiruError::WriteWithoutUniqueMut { .. })
+        ));
+    }
+
+    #[test]
+    fn reborrow_is_bounded_by_its_parent() {
+        let (mut state, region) = function_with_heap();
+        let value = state.allocate_value("v".to_string(), region, Vec::new(), false).unwrap();
+        let other = state.allocate_value("w".to_string(), region, Vec::new(), false).unwrap();
+        let outer = state.current_scope();
+        let lifetime = state.create_lifetime("l".to_string(), outer, 0).unwrap();
+
+        let inner = state.enter_scope(ScopeKind::Block, None);
+        let short = state.create_lifetime("short".to_string(), inner, 0).unwrap();
+        let parent = state
+            .create_capability(CapabilityKind::SharedRead, value, short, 0, None)
+            .unwrap();
+
+        let codes: Vec<_> = [
+            (CapabilityKind::UniqueMut, value, short),
+            (CapabilityKind::SharedRead, other, short),
+            (CapabilityKind::SharedRead, value, lifetime),
+        ]
+        .into_iter()
+        .map(|(kind, value, lifetime)| {
+            state.create_capability(kind, value, lifetime, 0, Some(parent)).unwrap_err().code()
+        })
+        .collect();
+        assert_eq!(codes, ["CH0039", "CH0042", "CH0040"]);
+    }
+
+    #[test]
+    fn reborrow_suspends_its_parent() {
+        let (mut state, region) = function_with_heap();
+        let value = state.allocate_value("v".to_string(), region, Vec::new(), false).unwrap();
+        let scope = state.current_scope();
+        let lifetime = state.create_lifetime("l".to_string(), scope, 0).unwrap();
+        let parent = state
+            .create_capability(CapabilityKind::UniqueMut, value, lifetime, 0, None)
+            .unwrap();
+        let child = state
+            .create_capability(CapabilityKind::SharedRead, value, lifetime, 0, Some(parent))
+            .unwrap();
+
+        assert!(state.access_value(value, Access::Read).is_ok());
+        assert!(matches!(
+            state.access_value(value, Access::Write),
+            Err(ChiruError::CapabilitySuspended { .. })
+        ));
+        state.release_capability(child).unwrap();
+        assert!(state.access_value(value, Access::Write).is_ok());
+    }
+
+    #[test]
+    fn scope_exit_reports_leaked_heap_value() {
+        let (mut state, region) = function_with_heap();
+        state.allocate_value("v".to_string(), region, Vec::new(), false).unwrap();
+
+        let errors = state.exit_scope().unwrap();
+        assert_eq!(errors.iter().map(ChiruError::code).collect::<Vec<_>>(), ["CH0043"]);
+    }
+
+    #[test]
+    fn region_ends_with_its_scope() {
+        let mut state = InterpreterState::new();
+        state.declare_default_phase();
+        let outer = state.enter_scope(ScopeKind::Function, Some("f".to_string()));
+        let lifetime = state.create_lifetime("l".to_string(), outer, 0).unwrap();
+
+        state.enter_scope(ScopeKind::Block, None);
+        let region = state
+            .declare_region("s".to_string(), RegionKind::Stack, Label::Public)
+            .unwrap();
+        let value = state.allocate_value("v".to_string(), region, Vec::new(), false).unwrap();
+        assert_eq!(
+            state.create_capability(CapabilityKind::SharedRead, value, lifetime, 0, None)
+                .unwrap_err()
+                .code(),
+            "CH0045",
+        );
+        state.move_value(value, outer).unwrap();
+
+        let errors = state.exit_scope().unwrap();
+        assert_eq!(errors.iter().map(ChiruError::code).collect::<Vec<_>>(), ["CH0044"]);
+        assert!(matches!(
+            state.allocate_value("w".to_string(), region, Vec::new(), false),
+            Err(ChiruError::InactiveRegion { .. })
+        ));
+    }
+
+    #[test]
+    fn secret_must_be_zeroized_before_drop() {
+        let (mut state, region) = function_with_heap();
+        let value = state.allocate_value("k".to_string(), region, Vec::new(), true).unwrap();
+
+        assert!(matches!(state.drop_value(value), Err(ChiruError::SecretNotZeroized { .. })));
+
+        let scope = state.current_scope();
+        let lifetime = state.create_lifetime("l".to_string(), scope, 0).unwrap();
+        let cap = state
+            .create_capability(CapabilityKind::UniqueMut, value, lifetime, 0, None)
+            .unwrap();
+        state.zeroize_value(value).unwrap();
+        state.release_capability(cap).unwrap();
+        assert!(state.drop_value(value).is_ok());
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt;
+
+use crate::ast::Span;
+use crate::parser::ParseError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Ident(String),
+    Str(String),
+    LBrace,
+    RBrace,
+    LParen,
+    RParen,
+    Colon,
+    Comma,
+    Newline,
+    Eof,
+}
+
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Ident(s) => write!(f, "`{}`", s),
+            TokenKind::Str(s) => write!(f, "\"{}\"", s),
+            TokenKind::LBrace => write!(f, "`{{`"),
+            TokenKind::RBrace => write!(f, "`}}`"),
+            TokenKind::LParen => write!(f, "`(`"),
+            TokenKind::RParen => write!(f, "`)`"),
+            TokenKind::Colon => write!(f, "`:`"),
+            TokenKind::Comma => write!(f, "`,`"),
+            TokenKind::Newline => write!(f, "end of line"),
+            TokenKind::Eof => write!(f, "end of file"),
+        }
+    }
+}
+
+/// Split source text into tokens, tracking the line and column of each.
+///
+/// Newlines are significant: a statement ends at the end of its line.
+pub fn tokenize(file: &str, source: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    let mut line = 1;
+    let mut column = 1;
+
+    while let Some(&c) = chars.peek() {
+        let span = Span { line, column };
+
+        if c == '\n' {
+            chars.next();
+            tokens.push(Token { kind: TokenKind::Newline, span });
+            line += 1;
+            column = 1;
+            continue;
+        }
+
+        if c.is_whitespace() {
+            chars.next();
+            column += 1;
+            continue;
+        }
+
+        let punct = match c {
+            '{' => Some(TokenKind::LBrace),
+            '}' => Some(TokenKind::RBrace),
+            '(' => Some(TokenKind::LParen),
+            ')' => Some(TokenKind::RParen),
+            ':' => Some(TokenKind::Colon),
+            ',' => Some(TokenKind::Comma),
+            _ => None,
+        };
+        if let Some(kind) = punct {
+            chars.next();
+            column += 1;
+            tokens.push(Token { kind, span });
+            continue;
+        }
+
+        if c == '"' {
+            chars.next();
+            column += 1;
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => {
+                        column += 1;
+                        break;
+                    }
+                    Some('\n') | None => {
+                        return Err(ParseError {
+                            file: file.to_string(),
+                            line: span.line,
+                            column: span.column,
+                            token: format!("\"{}", text),
+                            message: "unterminated string literal".into(),
+                        });
+                    }
+                    Some(ch) => {
+                        column += 1;
+                        text.push(ch);
+                    }
+                }
+            }
+            tokens.push(Token { kind: TokenKind::Str(text), span });
+            continue;
+        }
+
+        if c.is_alphanumeric() || c == '_' {
+            let mut word = String::new();
+            while let Some(&ch) = chars.peek() {
+                if !(ch.is_alphanumeric() || ch == '_') {
+                    break;
+                }
+                word.push(ch);
+                chars.next();
+                column += 1;
+            }
+            tokens.push(Token { kind: TokenKind::Ident(word), span });
+            continue;
+        }
+
+        return Err(ParseError {
+            file: file.to_string(),
+            line,
+            column,
+            token: c.to_string(),
+            message: "unexpected character".into(),
+        });
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        span: Span { line, column },
+    });
+
+    Ok(tokens)
+}
@@ -3,16 +3,124 @@ mod scope;
 mod interpreter;
 mod report;
 mod ast;
+mod lexer;
 mod parser;
 mod exec;
+mod error;
+mod explain;
+mod violation;
 
 use std::env;
 use std::fs;
 use std::process;
 
+use serde_json::json;
+
 use interpreter::InterpreterState;
 use report::SafetyReport;
-use exec::{execute, ExecContext};
+use exec::{execute_program, ExecContext};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Json,
+}
+
+struct Options {
+    file: String,
+    format: Format,
+    output: Option<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut file = None;
+    let mut format = Format::Text;
+    let mut output = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = match iter.next().map(String::as_str) {
+                    Some("text") => Format::Text,
+                    Some("json") => Format::Json,
+                    Some(other) => return Err(format!("Unknown format: {}", other)),
+                    None => return Err("--format requires a value (text|json)".into()),
+                };
+            }
+            "--output" => {
+                let path = iter.next().ok_or("--output requires a file path")?;
+                output = Some(path.clone());
+            }
+            flag if flag.starts_with("--") => {
+                return Err(format!("Unknown option: {}", flag));
+            }
+            path => {
+                if file.is_some() {
+                    return Err("Only one input file may be given".into());
+                }
+                file = Some(path.to_string());
+            }
+        }
+    }
+
+    Ok(Options {
+        file: file.ok_or("No input file given")?,
+        format,
+        output,
+    })
+}
+
+/// Write rendered output to `--output` if given, otherwise to stdout.
+fn emit(opts: &Options, rendered: &str) {
+    match &opts.output {
+        Some(path) => {
+            if let Err(e) = fs::write(path, format!("{}\n", rendered)) {
+                eprintln!("Failed to write {}: {}", path, e);
+                process::exit(3);
+            }
+        }
+        None => println!("{}", rendered),
+    }
+}
+
+/// Report an error and exit. In JSON mode the error is emitted as an
+/// object in place of the report; in text mode it goes to stderr.
+fn fail(opts: &Options, code: i32, text: String, error: serde_json::Value) -> ! {
+    match opts.format {
+        Format::Text => eprintln!("{}", text),
+        Format::Json => emit(opts, &serde_json::to_string_pretty(&error).unwrap()),
+    }
+    process::exit(code);
+}
+
+/// `chiru explain <code>`: print the long-form explanation of an error
+/// code, or list all codes when none is given.
+fn explain_command(args: &[String]) -> ! {
+    match args {
+        [] => {
+            for e in explain::EXPLANATIONS {
+                println!("{}  {}", e.code, e.title);
+            }
+            process::exit(0);
+        }
+        [code] => match explain::lookup(code) {
+            Some(e) => {
+                println!("{}", explain::render(e));
+                process::exit(0);
+            }
+            None => {
+                eprintln!("Unknown error code: {}", code);
+                eprintln!("Run `chiru explain` to list all codes.");
+                process::exit(3);
+            }
+        },
+        _ => {
+            eprintln!("Usage: chiru explain <code>");
+            process::exit(3);
+        }
+    }
+}
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -27,48 +135,116 @@ fn main() {
                 println!("Chiru — verification-first systems language");
                 println!();
                 println!("Usage:");
-                println!("  chiru <file.chiru>     Verify a Chiru file");
-                println!("  chiru --version        Show version");
-                println!("  chiru --help           Show this help");
+                println!("  chiru [options] <file.chiru>  Verify a Chiru file");
+                println!("  chiru explain <code>          Explain an error code, e.g. CH0012");
+                println!("  chiru --version               Show version");
+                println!("  chiru --help                  Show this help");
+                println!();
+                println!("Options:");
+                println!("  --format text|json            Report format (default: text)");
+                println!("  --output <file>               Write the report to a file");
                 process::exit(0);
             }
             _ => {}
         }
     }
 
-    if args.len() != 2 {
-        eprintln!("Usage: chiru <file.chiru>");
-        process::exit(3);
+    if args.get(1).map(String::as_str) == Some("explain") {
+        explain_command(&args[2..]);
     }
 
-    let source = match fs::read_to_string(&args[1]) {
-        Ok(s) => s,
+    let opts = match parse_args(&args[1..]) {
+        Ok(opts) => opts,
         Err(e) => {
-            eprintln!("Failed to read file: {}", e);
+            eprintln!("{}", e);
+            eprintln!("Usage: chiru [--format text|json] [--output <file>] <file.chiru>");
             process::exit(3);
         }
     };
 
-    let ast = match parser::parse(&source) {
+    let source = match fs::read_to_string(&opts.file) {
+        Ok(s) => s,
+        Err(e) => fail(
+            &opts,
+            3,
+            format!("Failed to read file: {}", e),
+            json!({ "error": "io", "file": opts.file, "message": e.to_string() }),
+        ),
+    };
+
+    let ast = match parser::parse(&opts.file, &source) {
         Ok(ast) => ast,
-        Err(e) => {
-            eprintln!("Parse error: {}", e);
-            process::exit(3);
-        }
+        Err(e) => fail(
+            &opts,
+            3,
+            format!("Parse error: {}", e),
+            json!({
+                "error": "parse",
+                "file": e.file,
+                "line": e.line,
+                "column": e.column,
+                "token": e.token,
+                "message": e.message,
+            }),
+        ),
     };
 
     let mut state = InterpreterState::new();
     let mut ctx = ExecContext::new();
 
-    if let Err(e) = execute(&ast, &mut state, &mut ctx) {
-        eprintln!("Semantic error:\n{}", e);
-        process::exit(2);
+    execute_program(&ast, &mut state, &mut ctx);
+
+    let report = SafetyReport::generate(&state, &ctx.summaries());
+
+    let rendered = match opts.format {
+        Format::Text => report.render(&state),
+        Format::Json => report.to_json(),
+    };
+    emit(&opts, &rendered);
+
+    process::exit(report.exit_code());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
     }
 
-    let report = SafetyReport::generate(&state);
-    report.print(&state);
+    #[test]
+    fn options_are_parsed_in_any_order() {
+        let opts = parse_args(&args(&["--output", "r.json", "p.chiru", "--format", "json"]))
+            .unwrap();
+        assert_eq!(opts.file, "p.chiru");
+        assert!(opts.format == Format::Json);
+        assert_eq!(opts.output.as_deref(), Some("r.json"));
 
-    let code = report.exit_code();
-    eprintln!("DEBUG: exit code = {}", code);
-    process::exit(code);
+        let opts = parse_args(&args(&["p.chiru"])).unwrap();
+        assert!(opts.format == Format::Text);
+        assert!(opts.output.is_none());
+    }
+
+    #[test]
+    fn bad_options_are_rejected() {
+        assert!(parse_args(&args(&["--format", "xml", "p.chiru"])).is_err());
+        assert!(parse_args(&args(&["p.chiru", "--output"])).is_err());
+        assert!(parse_args(&args(&["--verbose", "p.chiru"])).is_err());
+        assert!(parse_args(&args(&["a.chiru", "b.chiru"])).is_err());
+        assert!(parse_args(&args(&["--format", "json"])).is_err());
+    }
+
+    #[test]
+    fn output_file_receives_the_rendered_report() {
+        let path = env::temp_dir().join(format!("chiru-emit-{}.json", process::id()));
+        let opts = Options {
+            file: "p.chiru".to_string(),
+            format: Format::Json,
+            output: Some(path.to_string_lossy().into_owned()),
+        };
+        emit(&opts, "{}");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{}\n");
+        fs::remove_file(&path).unwrap();
+    }
 }
@@ -1,115 +1,619 @@
-use crate::ast::AstNode;
-
-pub fn parse(source: &str) -> Result<Vec<AstNode>, String> {
-    let mut lines = source.lines().enumerate().peekable();
-    parse_block(&mut lines)
-}
-
-fn parse_block<'a, I>(lines: &mut std::iter::Peekable<I>) -> Result<Vec<AstNode>, String>
-where
-    I: Iterator<Item = (usize, &'a str)>,
-{
-    let mut nodes = Vec::new();
-
-    while let Some((_, line)) = lines.peek() {
-        let line = line.trim();
-
-        if line.is_empty() {
-            lines.next();
-            continue;
-        }
-
-        if line == "}" {
-            lines.next();
-            break;
-        }
-
-        nodes.push(parse_line(lines)?);
-    }
-
-    Ok(nodes)
-}
-
-fn parse_line<'a, I>(lines: &mut std::iter::Peekable<I>) -> Result<AstNode, String>
-where
-    I: Iterator<Item = (usize, &'a str)>,
-{
-    let (_, raw) = lines.next().unwrap();
-    let line = raw.trim();
-
-    let parts: Vec<&str> = line.split_whitespace().collect();
-
-    match parts[0] {
-        "function" => {
-            let name = parts[1].to_string();
-            expect_brace(raw, lines)?;
-            let body = parse_block(lines)?;
-            Ok(AstNode::Function { name, body })
-        }
-
-        "unsafe" => {
-            expect_brace(raw,lines)?;
-            let body = parse_block(lines)?;
-            Ok(AstNode::Unsafe { body })
-        }
-
-        "region" => Ok(AstNode::Region {
-            kind: parts[1].to_string(),
-            name: parts[2].to_string(),
-        }),
-
-        "lifetime" => Ok(AstNode::Lifetime {
-            name: parts[1].to_string(),
-            scope: parts[4].to_string(),
-        }),
-
-        "let" => Ok(AstNode::Let {
-            name: parts[1].to_string(),
-            region: parts[3].to_string(),
-        }),
-
-        "capability" => Ok(AstNode::Capability {
-            kind: parts[1].to_string(),
-            value: parts[2].to_string(),
-            lifetime: parts[4].to_string(),
-        }),
-
-        "drop" => Ok(AstNode::Drop {
-            value: parts[1].to_string(),
-        }),
-
-        "assume" => Ok(AstNode::Assume {
-            text: raw.split('"').nth(1).unwrap().to_string(),
-        }),
-
-        _ => Err(format!("Unknown syntax: {}", raw)),
-    }
-}
-
-fn expect_brace<'a, I>(
-    current_line: &str,
-    lines: &mut std::iter::Peekable<I>,
-) -> Result<(), String>
-where
-    I: Iterator<Item = (usize, &'a str)>,
-{
-    // Case 1: brace is on the same line
-    if current_line.trim_end().ends_with('{') {
-        return Ok(());
-    }
-
-    // Case 2: brace is on the next line
-    while let Some((_, next)) = lines.next() {
-        let trimmed = next.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-        if trimmed == "{" {
-            return Ok(());
-        }
-        return Err("Expected '{'".into());
-    }
-
-    Err("Expected '{'".into())
-}
-
+use std::fmt;
+
+use crate::ast::{AstNode, NodeKind, CapabilityRef, Param};
+use crate::lexer::{tokenize, Token, TokenKind};
+
+/// A syntax error with the location of the offending token.
+#[derive(Debug)]
+pub struct ParseError {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub token: String,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}, found {}",
+            self.file, self.line, self.column, self.message, self.token
+        )
+    }
+}
+
+pub fn parse(file: &str, source: &str) -> Result<Vec<AstNode>, ParseError> {
+    let tokens = tokenize(file, source)?;
+    let mut parser = Parser {
+        file,
+        tokens,
+        pos: 0,
+    };
+    parser.parse_program()
+}
+
+struct Parser<'a> {
+    file: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    // =====================
+    // TOKEN STREAM
+    // =====================
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        if token.kind != TokenKind::Eof {
+            self.pos += 1;
+        }
+        token
+    }
+
+    /// The next token that is not a newline, without consuming anything.
+    fn peek_past_newlines(&self) -> &Token {
+        self.tokens[self.pos..].iter()
+            .find(|t| t.kind != TokenKind::Newline)
+            .expect("Token stream ends with Eof")
+    }
+
+    fn skip_newlines(&mut self) {
+        while self.peek().kind == TokenKind::Newline {
+            self.advance();
+        }
+    }
+
+    fn error(&self, token: &Token, message: impl Into<String>) -> ParseError {
+        ParseError {
+            file: self.file.to_string(),
+            line: token.span.line,
+            column: token.span.column,
+            token: token.kind.to_string(),
+            message: message.into(),
+        }
+    }
+
+    fn expect_ident(&mut self, what: &str) -> Result<String, ParseError> {
+        let token = self.advance();
+        match token.kind {
+            TokenKind::Ident(name) => Ok(name),
+            _ => Err(self.error(&token, format!("expected {}", what))),
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        let token = self.advance();
+        match &token.kind {
+            TokenKind::Ident(word) if word == keyword => Ok(()),
+            _ => Err(self.error(&token, format!("expected `{}`", keyword))),
+        }
+    }
+
+    fn expect_string(&mut self, what: &str) -> Result<String, ParseError> {
+        let token = self.advance();
+        match token.kind {
+            TokenKind::Str(text) => Ok(text),
+            _ => Err(self.error(&token, format!("expected {}", what))),
+        }
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.peek().kind, TokenKind::Ident(word) if word == keyword)
+    }
+
+    /// `name (, name)*`
+    fn parse_name_list(&mut self, what: &str) -> Result<Vec<String>, ParseError> {
+        let mut names = vec![self.expect_ident(what)?];
+        while self.peek().kind == TokenKind::Comma {
+            self.advance();
+            names.push(self.expect_ident(what)?);
+        }
+        Ok(names)
+    }
+
+    /// `NAME` or `KIND VALUE`
+    fn parse_capability_ref(&mut self) -> Result<CapabilityRef, ParseError> {
+        let first = self.expect_ident("capability name or kind")?;
+        match &self.peek().kind {
+            TokenKind::Ident(word) if !matches!(word.as_str(), "to" | "as") => {
+                Ok(CapabilityRef::Kind {
+                    kind: first,
+                    value: self.expect_ident("value name")?,
+                })
+            }
+            _ => Ok(CapabilityRef::Named(first)),
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<(), ParseError> {
+        let token = self.advance();
+        if token.kind != kind {
+            return Err(self.error(&token, format!("expected {}", kind)));
+        }
+        Ok(())
+    }
+
+    /// `( [name (, name)*] )`
+    fn parse_args(&mut self) -> Result<Vec<String>, ParseError> {
+        self.expect(TokenKind::LParen)?;
+        if self.peek().kind == TokenKind::RParen {
+            self.advance();
+            return Ok(Vec::new());
+        }
+        let args = self.parse_name_list("argument name")?;
+        self.expect(TokenKind::RParen)?;
+        Ok(args)
+    }
+
+    /// `( [name: KIND (, name: KIND)*] )`
+    fn parse_params(&mut self) -> Result<Vec<Param>, ParseError> {
+        self.expect(TokenKind::LParen)?;
+        let mut params = Vec::new();
+        if self.peek().kind == TokenKind::RParen {
+            self.advance();
+            return Ok(params);
+        }
+        loop {
+            let name = self.expect_ident("parameter name")?;
+            self.expect(TokenKind::Colon)?;
+            let capability = self.expect_ident("capability kind")?;
+            params.push(Param { name, capability });
+            if self.peek().kind != TokenKind::Comma {
+                break;
+            }
+            self.advance();
+        }
+        self.expect(TokenKind::RParen)?;
+        Ok(params)
+    }
+
+    /// A statement ends at a newline, a closing brace or the end of file.
+    /// The closing brace is left for the enclosing block to consume.
+    fn end_statement(&mut self) -> Result<(), ParseError> {
+        let token = self.peek().clone();
+        match token.kind {
+            TokenKind::Newline => {
+                self.advance();
+                Ok(())
+            }
+            TokenKind::RBrace | TokenKind::Eof => Ok(()),
+            _ => Err(self.error(&token, "expected end of statement")),
+        }
+    }
+
+    // =====================
+    // GRAMMAR
+    // =====================
+
+    fn parse_program(&mut self) -> Result<Vec<AstNode>, ParseError> {
+        let mut nodes = Vec::new();
+
+        loop {
+            self.skip_newlines();
+            let token = self.peek().clone();
+            match token.kind {
+                TokenKind::Eof => break,
+                TokenKind::RBrace => {
+                    return Err(self.error(&token, "unmatched closing brace"));
+                }
+                _ => nodes.push(self.parse_statement()?),
+            }
+        }
+
+        Ok(nodes)
+    }
+
+    /// `{` statement* `}` — the opening brace may follow on a later line.
+    fn parse_block(&mut self) -> Result<Vec<AstNode>, ParseError> {
+        let nodes = self.parse_braced()?;
+        self.end_statement()?;
+        Ok(nodes)
+    }
+
+    /// A block without the end of statement after it, for `} else {`.
+    fn parse_braced(&mut self) -> Result<Vec<AstNode>, ParseError> {
+        self.skip_newlines();
+        let open = self.advance();
+        if open.kind != TokenKind::LBrace {
+            return Err(self.error(&open, "expected `{`"));
+        }
+
+        let mut nodes = Vec::new();
+
+        loop {
+            self.skip_newlines();
+            let token = self.peek().clone();
+            match token.kind {
+                TokenKind::RBrace => {
+                    self.advance();
+                    break;
+                }
+                TokenKind::Eof => {
+                    return Err(self.error(&token, "unclosed block, expected `}`"));
+                }
+                _ => nodes.push(self.parse_statement()?),
+            }
+        }
+
+        Ok(nodes)
+    }
+
+    fn parse_statement(&mut self) -> Result<AstNode, ParseError> {
+        let token = self.advance();
+        let span = token.span;
+
+        let keyword = match &token.kind {
+            TokenKind::Ident(word) => word.clone(),
+            _ => return Err(self.error(&token, "expected a statement")),
+        };
+
+        let kind = match keyword.as_str() {
+            "function" => {
+                let name = self.expect_ident("function name")?;
+                let params = if self.peek().kind == TokenKind::LParen {
+                    self.parse_params()?
+                } else {
+                    Vec::new()
+                };
+                let body = self.parse_block()?;
+                return Ok(AstNode {
+                    kind: NodeKind::Function { name, params, body },
+                    span,
+                });
+            }
+
+            "block" => {
+                let name = match &self.peek().kind {
+                    TokenKind::Ident(_) => Some(self.expect_ident("block name")?),
+                    _ => None,
+                };
+                let body = self.parse_block()?;
+                return Ok(AstNode {
+                    kind: NodeKind::Block { name, body },
+                    span,
+                });
+            }
+
+            "unsafe" => {
+                let body = self.parse_block()?;
+                return Ok(AstNode {
+                    kind: NodeKind::Unsafe { body },
+                    span,
+                });
+            }
+
+            "declassify" => {
+                let body = self.parse_block()?;
+                return Ok(AstNode {
+                    kind: NodeKind::Declassify { body },
+                    span,
+                });
+            }
+
+            "if" => {
+                let condition = self.expect_ident("condition name")?;
+                let then_body = self.parse_braced()?;
+                // `else` may follow the closing brace or start the next line
+                let has_else = matches!(
+                    &self.peek_past_newlines().kind,
+                    TokenKind::Ident(word) if word == "else"
+                );
+                let else_body = if has_else {
+                    self.skip_newlines();
+                    self.advance();
+                    self.parse_block()?
+                } else {
+                    self.end_statement()?;
+                    Vec::new()
+                };
+                return Ok(AstNode {
+                    kind: NodeKind::If { condition, then_body, else_body },
+                    span,
+                });
+            }
+
+            "loop" => {
+                let body = self.parse_block()?;
+                return Ok(AstNode {
+                    kind: NodeKind::Loop { count: None, body },
+                    span,
+                });
+            }
+
+            "repeat" => {
+                let token = self.advance();
+                let count = match &token.kind {
+                    TokenKind::Ident(word) => word.parse::<usize>().ok(),
+                    _ => None,
+                };
+                let count = count.ok_or_else(|| self.error(&token, "expected iteration count"))?;
+                let body = self.parse_block()?;
+                return Ok(AstNode {
+                    kind: NodeKind::Loop { count: Some(count), body },
+                    span,
+                });
+            }
+
+            "phase" => NodeKind::PhaseDecl {
+                name: self.expect_ident("phase name")?,
+            },
+
+            "advance" => {
+                self.expect_keyword("to")?;
+                let phase = self.expect_ident("phase name")?;
+                NodeKind::Advance { phase }
+            }
+
+            "spawn" => {
+                let body = self.parse_block()?;
+                return Ok(AstNode {
+                    kind: NodeKind::Spawn { body },
+                    span,
+                });
+            }
+
+            "region" => {
+                let kind = self.expect_ident("region kind")?;
+                let name = self.expect_ident("region name")?;
+                let label = if self.peek_keyword("label") {
+                    self.advance();
+                    Some(self.expect_ident("security label")?)
+                } else {
+                    None
+                };
+                let assumption = if self.peek_keyword("assume") {
+                    self.advance();
+                    Some(self.expect_string("assumption text in quotes")?)
+                } else {
+                    None
+                };
+                NodeKind::Region {
+                    kind,
+                    name,
+                    label,
+                    assumption,
+                }
+            }
+
+            "lifetime" => {
+                let name = self.expect_ident("lifetime name")?;
+                self.expect_keyword("bound")?;
+                self.expect_keyword("to")?;
+                let scope = self.expect_ident("scope name")?;
+                NodeKind::Lifetime { name, scope }
+            }
+
+            "let" => {
+                let mut name = self.expect_ident("value name")?;
+                // `secret` is a qualifier unless it is the value's own name
+                let secret = name == "secret"
+                    && !self.peek_keyword("in")
+                    && !self.peek_keyword("from");
+                if secret {
+                    name = self.expect_ident("value name")?;
+                }
+                let sources = if self.peek_keyword("from") {
+                    self.advance();
+                    self.parse_name_list("value name")?
+                } else {
+                    Vec::new()
+                };
+                self.expect_keyword("in")?;
+                let region = self.expect_ident("region name")?;
+                NodeKind::Let {
+                    name,
+                    region,
+                    sources,
+                    secret,
+                }
+            }
+
+            "capability" => {
+                let kind = self.expect_ident("capability kind")?;
+                let value = self.expect_ident("value name")?;
+                self.expect_keyword("during")?;
+                let lifetime = self.expect_ident("lifetime name")?;
+                let parent = if self.peek_keyword("from") {
+                    self.advance();
+                    Some(self.parse_capability_ref()?)
+                } else {
+                    None
+                };
+                let name = if self.peek_keyword("as") {
+                    self.advance();
+                    Some(self.expect_ident("capability name")?)
+                } else {
+                    None
+                };
+                NodeKind::Capability {
+                    kind,
+                    value,
+                    lifetime,
+                    name,
+                    parent,
+                }
+            }
+
+            "release" => NodeKind::Release {
+                target: self.parse_capability_ref()?,
+            },
+
+            "downgrade" => {
+                let target = self.parse_capability_ref()?;
+                self.expect_keyword("to")?;
+                let kind = self.expect_ident("capability kind")?;
+                NodeKind::Downgrade { target, kind }
+            }
+
+            "drop" => NodeKind::Drop {
+                value: self.expect_ident("value name")?,
+            },
+
+            "copy" => {
+                let from = self.expect_ident("value name")?;
+                self.expect_keyword("into")?;
+                let into = self.expect_ident("value name")?;
+                NodeKind::Copy { from, into }
+            }
+
+            "zeroize" => NodeKind::Zeroize {
+                value: self.expect_ident("value name")?,
+            },
+
+            "return" => {
+                let value = self.expect_ident("value name")?;
+                self.expect_keyword("to")?;
+                let region = self.expect_ident("region name")?;
+                NodeKind::Return { value, region }
+            }
+
+            "move" => {
+                let value = self.expect_ident("value name")?;
+                self.expect_keyword("to")?;
+                let target = self.expect_ident("scope name")?;
+                NodeKind::Move { value, target }
+            }
+
+            "read" => NodeKind::Read {
+                value: self.expect_ident("value name")?,
+            },
+
+            "write" => NodeKind::Write {
+                value: self.expect_ident("value name")?,
+            },
+
+            "call" => {
+                let function = self.expect_ident("function name")?;
+                if self.peek().kind == TokenKind::LParen {
+                    let args = self.parse_args()?;
+                    NodeKind::CallFunction { function, args }
+                } else {
+                    self.expect_keyword("with")?;
+                    let values = self.parse_name_list("value name")?;
+                    NodeKind::Call { function, values }
+                }
+            }
+
+            "assume" => {
+                let text = self.expect_string("assumption text in quotes")?;
+                let affects = if self.peek_keyword("affects") {
+                    self.advance();
+                    Some(self.parse_name_list("value name")?)
+                } else {
+                    None
+                };
+                NodeKind::Assume { text, affects }
+            }
+
+            _ => return Err(self.error(&token, "unknown syntax")),
+        };
+
+        self.end_statement()?;
+        Ok(AstNode { kind, span })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_ok(source: &str) -> Vec<AstNode> {
+        parse("test.chiru", source).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    #[test]
+    fn parses_function_parameters() {
+        let ast = parse_ok("function sign(key: SharedRead, out: Own) {\n}\n");
+        match &ast[0].kind {
+            NodeKind::Function { name, params, .. } => {
+                assert_eq!(name, "sign");
+                let names: Vec<_> = params.iter().map(|p| p.name.as_str()).collect();
+                assert_eq!(names, ["key", "out"]);
+            }
+            other => panic!("expected a function, got {}", other),
+        }
+    }
+
+    #[test]
+    fn parses_if_with_else() {
+        let ast = parse_ok("if ok {\n  drop a\n} else {\n  drop b\n}\n");
+        match &ast[0].kind {
+            NodeKind::If { condition, then_body, else_body } => {
+                assert_eq!(condition, "ok");
+                assert_eq!(then_body.len(), 1);
+                assert_eq!(else_body.len(), 1);
+            }
+            other => panic!("expected an if, got {}", other),
+        }
+    }
+
+    #[test]
+    fn parses_else_on_the_next_line() {
+        let ast = parse_ok("if ok {\n  drop a\n}\nelse {\n  drop b\n}\ndrop c\n");
+        assert_eq!(ast.len(), 2);
+        match &ast[0].kind {
+            NodeKind::If { else_body, .. } => assert_eq!(else_body.len(), 1),
+            other => panic!("expected an if, got {}", other),
+        }
+    }
+
+    #[test]
+    fn parses_loops() {
+        let ast = parse_ok("loop {\n}\nrepeat 3 {\n}\n");
+        assert!(matches!(ast[0].kind, NodeKind::Loop { count: None, .. }));
+        assert!(matches!(ast[1].kind, NodeKind::Loop { count: Some(3), .. }));
+    }
+
+    #[test]
+    fn rejects_repeat_without_count() {
+        let err = parse("test.chiru", "repeat {\n}\n").unwrap_err();
+        assert_eq!(err.message, "expected iteration count");
+    }
+
+    #[test]
+    fn parses_named_and_anonymous_blocks() {
+        let ast = parse_ok("block inner {\n}\nblock {\n}\n");
+        assert!(matches!(&ast[0].kind, NodeKind::Block { name: Some(n), .. } if n == "inner"));
+        assert!(matches!(ast[1].kind, NodeKind::Block { name: None, .. }));
+    }
+
+    fn parse_err(source: &str) -> ParseError {
+        match parse("test.chiru", source) {
+            Ok(_) => panic!("expected a parse error"),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn reports_where_a_name_is_missing() {
+        let err = parse_err("function f {\n  drop\n}\n");
+        assert_eq!(err.file, "test.chiru");
+        assert_eq!((err.line, err.column), (2, 7));
+        assert_eq!(err.token, "end of line");
+        assert_eq!(err.message, "expected value name");
+    }
+
+    #[test]
+    fn reports_the_unexpected_character() {
+        let err = parse_err("function f {\n  region heap h $\n}\n");
+        assert_eq!((err.line, err.column), (2, 17));
+        assert_eq!(err.token, "$");
+    }
+
+    #[test]
+    fn reports_an_unclosed_block_at_end_of_file() {
+        let err = parse_err("function f {\n  read a\n");
+        assert_eq!((err.line, err.column), (3, 1));
+        assert_eq!(err.token, "end of file");
+        assert_eq!(
+            err.to_string(),
+            "test.chiru:3:1: unclosed block, expected `}`, found end of file",
+        );
+    }
+}
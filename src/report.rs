@@ -1,192 +1,511 @@
-use serde::Serialize;
-
-use crate::interpreter::InterpreterState;
-use crate::graph::{ValueOrigin, UnsafeAssumptionNode};
-
-/// ===============================
-/// Public Safety Report (v0)
-/// ===============================
-///
-/// This is a *frozen* structure for Chiru v0.
-/// It is designed to be:
-/// - Human-readable
-/// - Machine-consumable
-/// - CI-friendly
-/// - Audit-grade
-///
-/// DO NOT embed engine logic here.
-/// DO NOT add inference.
-/// This file only *renders truth*.
-///
-
-#[derive(Serialize)]
-pub struct SafetyReport {
-    pub verdict: String,
-    pub summary: Summary,
-    pub unsafe_assumptions: Vec<UnsafeAssumptionNode>,
-    pub values: Vec<ValueSummary>,
-    ownership_ok: bool,
-    lifetimes_ok: bool,
-    capabilities_ok: bool,
-    destruction_ok: bool,
-    has_unsafe_assumptions: bool,
-}
-
-#[derive(Serialize)]
-pub struct Summary {
-    pub ownership: String,
-    pub lifetimes: String,
-    pub capabilities: String,
-    pub destruction: String,
-}
-
-#[derive(Serialize)]
-pub struct ValueSummary {
-    pub id: u64,
-    pub origin: String,
-    pub state: String,
-}
-
-impl SafetyReport {
-    /// Generate a SafetyReport from interpreter state.
-    ///
-    /// This function must NEVER fail.
-    /// If state exists, a report must be produced.
-    pub fn generate(state: &InterpreterState) -> Self {
-        let unsafe_assumptions: Vec<_> =
-            state.graph.unsafe_assumptions.values().cloned().collect();
-
-        let values: Vec<ValueSummary> = state
-            .graph
-            .values
-            .values()
-            .map(|v| ValueSummary {
-                id: v.id,
-                origin: match v.origin {
-                    ValueOrigin::Safe => "SAFE".to_string(),
-                    ValueOrigin::Unsafe => "UNSAFE".to_string(),
-                },
-                state: if v.alive {
-                    "ALIVE".to_string()
-                } else {
-                    "DESTROYED".to_string()
-                },
-            })
-            .collect();
-
-        let verdict = if unsafe_assumptions.is_empty() {
-            "SAFE"
-        } else {
-            "SAFE_IF_ASSUMPTIONS_HOLD"
-        };
-
-         let ownership_ok = true; // enforced during execution
-        let lifetimes_ok = state.graph.lifetimes.values().all(|l| !l.active);
-        let capabilities_ok = true; // conflicts rejected at creation
-        let destruction_ok = true; // double-drops rejected
-
-        let has_unsafe_assumptions = !state.graph.unsafe_assumptions.is_empty();
-
-        SafetyReport {
-            verdict: verdict.to_string(),
-            summary: Summary {
-                ownership: "VERIFIED".to_string(),
-                lifetimes: "VERIFIED".to_string(),
-                capabilities: "VERIFIED".to_string(),
-                destruction: "VERIFIED".to_string(),
-            },
-            unsafe_assumptions,
-            values,
-            ownership_ok,
-            lifetimes_ok,
-            capabilities_ok,
-            destruction_ok,
-            has_unsafe_assumptions,
-        }
-    }
-
-    /// Print the human-readable Safety Report
-     pub fn print(&self, state: &InterpreterState) {
-        println!("Chiru Safety Report");
-        println!("==================\n");
-
-        println!("Summary");
-        println!("-------");
-        println!("Ownership:     {}", if self.ownership_ok { "VERIFIED" } else { "FAILED" });
-        println!("Lifetimes:     {}", if self.lifetimes_ok { "VERIFIED" } else { "FAILED" });
-        println!("Capabilities:  {}", if self.capabilities_ok { "VERIFIED" } else { "FAILED" });
-        println!("Destruction:   {}", if self.destruction_ok { "VERIFIED" } else { "FAILED" });
-        println!();
-
-        // === PHASES ===
-        println!("Phases");
-        println!("------");
-        for phase in &state.phases {
-            let marker = if Some(phase.id) == state.current_phase {
-                " (active)"
-            } else {
-                ""
-            };
-            println!("[{}] {}{}", phase.id, phase.name, marker);
-        }
-        println!();
-
-        // === UNSAFE ASSUMPTIONS ===
-        println!("Unsafe Assumptions");
-        println!("------------------");
-        if state.graph.unsafe_assumptions.is_empty() {
-            println!("None");
-        } else {
-            for ua in state.graph.unsafe_assumptions.values() {
-                let phase_name = &state.phases[ua.phase].name;
-                println!(
-                    "[UA-{:03}] {}",
-                    ua.id,
-                    ua.description
-                );
-                println!("  Phase: {}", phase_name);
-                println!("  Scope: {}", ua.scope);
-                println!("  Affects: {:?}", ua.affected_values);
-            }
-        }
-        println!();
-
-        // === VALUES ===
-        println!("Values");
-        println!("------");
-        for value in state.graph.values.values() {
-            let status = if value.alive {
-                match value.origin {
-                    ValueOrigin::Safe => "SAFE",
-                    ValueOrigin::Unsafe => "UNSAFE",
-                }
-            } else {
-                "DESTROYED"
-            };
-
-            println!("{}: {}", value.id, status);
-        }
-        println!();
-
-        // === VERDICT ===
-        println!("Verdict");
-        println!("-------");
-        if !self.ownership_ok || !self.lifetimes_ok || !self.capabilities_ok || !self.destruction_ok {
-            println!("UNSAFE");
-        } else if self.has_unsafe_assumptions {
-            println!("SAFE_IF_ASSUMPTIONS_HOLD");
-        } else {
-            println!("SAFE");
-        }
-    }
-
-    pub fn exit_code(&self) -> i32 {
-        if !self.ownership_ok || !self.lifetimes_ok || !self.capabilities_ok || !self.destruction_ok {
-            2
-        } else if self.has_unsafe_assumptions {
-            1
-        } else {
-            0
-        }
-    }
-}
\ No newline at end of file
+use serde::Serialize;
+
+use crate::interpreter::InterpreterState;
+use crate::exec::FunctionSummary;
+use crate::graph::{ValueOrigin, UnsafeAssumptionNode};
+use crate::violation::{Violation, ViolationKind};
+use crate::ast::Span;
+
+/// ===============================
+/// Public Safety Report (v0)
+/// ===============================
+///
+/// This is a *frozen* structure for Chiru v0.
+/// It is designed to be:
+/// - Human-readable
+/// - Machine-consumable
+/// - CI-friendly
+/// - Audit-grade
+///
+/// DO NOT embed engine logic here.
+/// DO NOT add inference.
+/// This file only *renders truth*.
+///
+
+#[derive(Serialize)]
+pub struct SafetyReport {
+    pub verdict: String,
+    pub summary: Summary,
+    pub unsafe_assumptions: Vec<UnsafeAssumptionNode>,
+    pub values: Vec<ValueSummary>,
+    pub functions: Vec<FunctionReport>,
+    pub declassifications: Vec<DeclassificationSummary>,
+    pub violations: Vec<Violation>,
+    ownership_ok: bool,
+    lifetimes_ok: bool,
+    capabilities_ok: bool,
+    destruction_ok: bool,
+    declarations_ok: bool,
+    zeroization_ok: bool,
+    information_flow_ok: bool,
+    has_unsafe_assumptions: bool,
+}
+
+#[derive(Serialize)]
+pub struct Summary {
+    pub ownership: String,
+    pub lifetimes: String,
+    pub capabilities: String,
+    pub destruction: String,
+    pub declarations: String,
+    pub zeroization: String,
+    pub information_flow: String,
+}
+
+#[derive(Serialize)]
+pub struct ValueSummary {
+    pub id: u64,
+    pub name: String,
+    pub origin: String,
+    pub state: String,
+    pub secret: bool,
+    pub zeroized: bool,
+    pub derived_from: Vec<String>,
+    pub assumptions: Vec<AssumptionDependency>,
+}
+
+/// A function's verdict and the assumptions it depends on, through its body
+/// and every function it calls.
+#[derive(Serialize)]
+pub struct FunctionReport {
+    pub name: String,
+    pub verdict: String,
+    pub requires: Vec<String>,
+    pub consumes: Vec<String>,
+    pub moves: Vec<String>,
+    pub writes: Vec<String>,
+    pub flows: Vec<String>,
+    pub advances: Vec<String>,
+    pub calls: Vec<String>,
+    pub unsafe_calls: Vec<String>,
+    pub assumptions: Vec<u64>,
+}
+
+/// A point where a higher-labelled value was allowed to flow into a lower one.
+#[derive(Serialize)]
+pub struct DeclassificationSummary {
+    pub from: String,
+    pub to: String,
+    pub assumptions: Vec<u64>,
+    pub span: Span,
+}
+
+/// An unsafe assumption a value rests on. `via` names the values it was
+/// derived through, from the immediate source to the affected value; it is
+/// empty when the assumption affects the value directly.
+#[derive(Serialize)]
+pub struct AssumptionDependency {
+    pub id: u64,
+    pub via: Vec<String>,
+}
+
+impl SafetyReport {
+    /// Generate a SafetyReport from interpreter state.
+    ///
+    /// This function must NEVER fail.
+    /// If state exists, a report must be produced.
+    pub fn generate(state: &InterpreterState, summaries: &[&FunctionSummary]) -> Self {
+        let mut unsafe_assumptions: Vec<_> =
+            state.graph.unsafe_assumptions.values().cloned().collect();
+        unsafe_assumptions.sort_by_key(|ua| ua.id);
+
+        // A value a function moves out is reported once per call instead,
+        // and a parameter as each argument passed to it
+        let retracted: Vec<u64> = summaries.iter()
+            .flat_map(|f| f.moves.iter().map(|escape| escape.value))
+            .collect();
+
+        let mut values: Vec<ValueSummary> = state
+            .graph
+            .values
+            .values()
+            .filter(|v| !retracted.contains(&v.id) && !v.parameter)
+            .map(|v| ValueSummary {
+                id: v.id,
+                name: v.name.clone(),
+                origin: match v.origin {
+                    ValueOrigin::Safe => "SAFE".to_string(),
+                    ValueOrigin::Unsafe => "UNSAFE".to_string(),
+                },
+                state: if v.alive {
+                    "ALIVE".to_string()
+                } else if v.leaked {
+                    "LEAKED".to_string()
+                } else {
+                    "DESTROYED".to_string()
+                },
+                secret: v.secret,
+                zeroized: v.zeroized,
+                derived_from: v.derived_from.iter()
+                    .map(|id| state.graph.values[id].name.clone())
+                    .collect(),
+                assumptions: state.assumption_chain(v.id).into_iter()
+                    .map(|(id, via)| AssumptionDependency {
+                        id,
+                        via: via.iter().map(|v| state.graph.values[v].name.clone()).collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        values.sort_by_key(|v| v.id);
+
+        let functions = summaries.iter()
+            .map(|f| FunctionReport {
+                name: f.name.clone(),
+                verdict: if f.violations > 0 || !f.unsafe_calls.is_empty() {
+                    "UNSAFE"
+                } else if !f.depends_on.is_empty() {
+                    "SAFE_IF_ASSUMPTIONS_HOLD"
+                } else {
+                    "SAFE"
+                }
+                .to_string(),
+                requires: f.params.iter()
+                    .map(|(name, kind)| format!("{}: {:?}", name, kind))
+                    .collect(),
+                consumes: f.consumes.iter()
+                    .map(|(name, discharge)| format!("{} ({})", name, discharge))
+                    .collect(),
+                moves: f.moves.iter().map(|escape| escape.name.clone()).collect(),
+                writes: f.writes.clone(),
+                advances: f.advances.clone(),
+                flows: f.flows.iter().map(|(from, into)| format!("{} -> {}", from, into)).collect(),
+                calls: f.calls.clone(),
+                unsafe_calls: f.unsafe_calls.clone(),
+                assumptions: f.depends_on.clone(),
+            })
+            .collect();
+
+        let declassifications = state.declassifications.iter()
+            .map(|d| DeclassificationSummary {
+                from: state.graph.values[&d.from].name.clone(),
+                to: state.graph.values[&d.to].name.clone(),
+                assumptions: d.assumptions.clone(),
+                span: d.span,
+            })
+            .collect();
+
+        let violations = state.violations.clone();
+        let clean = |kind: ViolationKind| !violations.iter().any(|v| v.kind == kind);
+
+        let ownership_ok = clean(ViolationKind::Ownership);
+        let lifetimes_ok = clean(ViolationKind::Lifetimes);
+        let capabilities_ok = clean(ViolationKind::Capabilities);
+        let destruction_ok = clean(ViolationKind::Destruction);
+        let declarations_ok = clean(ViolationKind::Declarations);
+        let zeroization_ok = clean(ViolationKind::Zeroization);
+        let information_flow_ok = clean(ViolationKind::InformationFlow);
+
+        let has_unsafe_assumptions = !state.graph.unsafe_assumptions.is_empty();
+
+        let verdict = if !violations.is_empty() {
+            "UNSAFE"
+        } else if has_unsafe_assumptions {
+            "SAFE_IF_ASSUMPTIONS_HOLD"
+        } else {
+            "SAFE"
+        };
+
+        SafetyReport {
+            verdict: verdict.to_string(),
+            summary: Summary {
+                ownership: status(ownership_ok),
+                lifetimes: status(lifetimes_ok),
+                capabilities: status(capabilities_ok),
+                destruction: status(destruction_ok),
+                declarations: status(declarations_ok),
+                zeroization: status(zeroization_ok),
+                information_flow: status(information_flow_ok),
+            },
+            unsafe_assumptions,
+            values,
+            functions,
+            declassifications,
+            violations,
+            ownership_ok,
+            lifetimes_ok,
+            capabilities_ok,
+            destruction_ok,
+            declarations_ok,
+            zeroization_ok,
+            information_flow_ok,
+            has_unsafe_assumptions,
+        }
+    }
+
+    /// Render the human-readable Safety Report
+    pub fn render(&self, state: &InterpreterState) -> String {
+        let mut out = Vec::new();
+
+        out.push("Chiru Safety Report".to_string());
+        out.push("==================\n".to_string());
+
+        out.push("Summary".to_string());
+        out.push("-------".to_string());
+        out.push(format!("Ownership:     {}", self.summary.ownership));
+        out.push(format!("Lifetimes:     {}", self.summary.lifetimes));
+        out.push(format!("Capabilities:  {}", self.summary.capabilities));
+        out.push(format!("Destruction:   {}", self.summary.destruction));
+        out.push(format!("Declarations:  {}", self.summary.declarations));
+        out.push(format!("Zeroization:   {}", self.summary.zeroization));
+        out.push(format!("Info Flow:     {}", self.summary.information_flow));
+        out.push(String::new());
+
+        // === PHASES ===
+        out.push("Phases".to_string());
+        out.push("------".to_string());
+        for phase in &state.phases {
+            let implicit = if phase.implicit { " (implicit)" } else { "" };
+            let marker = if Some(phase.id) == state.current_phase {
+                " (active)"
+            } else {
+                ""
+            };
+            out.push(format!("[{}] {}{}{}", phase.id, phase.name, implicit, marker));
+        }
+        out.push(String::new());
+
+        // === UNSAFE ASSUMPTIONS ===
+        out.push("Unsafe Assumptions".to_string());
+        out.push("------------------".to_string());
+        if self.unsafe_assumptions.is_empty() {
+            out.push("None".to_string());
+        } else {
+            for ua in &self.unsafe_assumptions {
+                let phase_name = &state.phases[ua.phase].name;
+                out.push(format!(
+                    "[UA-{:03}] {}",
+                    ua.id,
+                    ua.description
+                ));
+                out.push(format!("  Phase: {}", phase_name));
+                out.push(format!("  Scope: {}", ua.scope));
+                let names: Vec<&str> = ua.affected_values.iter()
+                    .map(|id| state.graph.values[id].name.as_str())
+                    .collect();
+                let affects = if names.is_empty() {
+                    "none".to_string()
+                } else {
+                    names.join(", ")
+                };
+                let inferred = if ua.inferred { " (inferred from unsafe block)" } else { "" };
+                out.push(format!("  Affects: {}{}", affects, inferred));
+            }
+        }
+        out.push(String::new());
+
+        // === VALUES ===
+        out.push("Values".to_string());
+        out.push("------".to_string());
+        for value in &self.values {
+            // Unsafe origin stays visible after destruction
+            let status = if value.state == "ALIVE" {
+                value.origin.clone()
+            } else if value.origin == "UNSAFE" {
+                format!("{} (UNSAFE)", value.state)
+            } else {
+                value.state.clone()
+            };
+
+            out.push(format!("{} ({}): {}", value.id, value.name, status));
+            if value.secret {
+                let wiped = if value.zeroized { "zeroized" } else { "not zeroized" };
+                out.push(format!("  Secret: {}", wiped));
+            }
+            if !value.derived_from.is_empty() {
+                out.push(format!("  Derived from: {}", value.derived_from.join(", ")));
+            }
+            for dep in &value.assumptions {
+                if dep.via.is_empty() {
+                    out.push(format!("  Rests on: [UA-{:03}]", dep.id));
+                } else {
+                    let via = dep.via.join(" -> ");
+                    out.push(format!("  Rests on: [UA-{:03}] via {}", dep.id, via));
+                }
+            }
+        }
+        out.push(String::new());
+
+        // === FUNCTIONS ===
+        if !self.functions.is_empty() {
+            out.push("Functions".to_string());
+            out.push("---------".to_string());
+            for function in &self.functions {
+                out.push(format!("{}: {}", function.name, function.verdict));
+                let lines = [
+                    ("Requires", &function.requires),
+                    ("Consumes", &function.consumes),
+                    ("Moves", &function.moves),
+                    ("Writes", &function.writes),
+                    ("Flows", &function.flows),
+                    ("Advances", &function.advances),
+                    ("Calls", &function.calls),
+                    ("Unsafe calls", &function.unsafe_calls),
+                ];
+                for (label, items) in lines {
+                    if !items.is_empty() {
+                        out.push(format!("  {}: {}", label, items.join(", ")));
+                    }
+                }
+                if !function.assumptions.is_empty() {
+                    let ids: Vec<String> =
+                        function.assumptions.iter().map(|id| format!("[UA-{:03}]", id)).collect();
+                    out.push(format!("  Depends on: {}", ids.join(", ")));
+                }
+            }
+            out.push(String::new());
+        }
+
+        // === DECLASSIFICATIONS ===
+        if !self.declassifications.is_empty() {
+            out.push("Declassifications".to_string());
+            out.push("-----------------".to_string());
+            for (i, d) in self.declassifications.iter().enumerate() {
+                out.push(format!(
+                    "[D-{:03}] {} -> {} at line {}, column {}",
+                    i + 1,
+                    d.from,
+                    d.to,
+                    d.span.line,
+                    d.span.column
+                ));
+                let under: Vec<String> =
+                    d.assumptions.iter().map(|id| format!("[UA-{:03}]", id)).collect();
+                out.push(format!("  Under: {}", under.join(", ")));
+            }
+            out.push(String::new());
+        }
+
+        // === VIOLATIONS ===
+        if !self.violations.is_empty() {
+            out.push("Violations".to_string());
+            out.push("----------".to_string());
+            for (i, v) in self.violations.iter().enumerate() {
+                out.push(format!(
+                    "[V-{:03}] {} {:?} at line {}, column {}",
+                    i + 1,
+                    v.code,
+                    v.kind,
+                    v.span.line,
+                    v.span.column
+                ));
+                out.push(format!("  Statement: {}", v.statement));
+                for line in v.message.lines() {
+                    out.push(format!("  {}", line));
+                }
+            }
+            out.push(String::new());
+        }
+
+        // === VERDICT ===
+        out.push("Verdict".to_string());
+        out.push("-------".to_string());
+        out.push(self.verdict.clone());
+
+        out.join("\n")
+    }
+
+    /// Render the report as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("SafetyReport is always serializable")
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        if !self.violations.is_empty() {
+            2
+        } else if self.has_unsafe_assumptions {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+fn status(ok: bool) -> String {
+    if ok { "VERIFIED" } else { "FAILED" }.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::{execute_program, ExecContext};
+    use crate::parser::parse;
+
+    fn report(source: &str) -> (SafetyReport, InterpreterState) {
+        let ast = parse("test.chiru", source).unwrap_or_else(|e| panic!("{}", e));
+        let mut state = InterpreterState::new();
+        let mut ctx = ExecContext::new();
+        execute_program(&ast, &mut state, &mut ctx);
+        (SafetyReport::generate(&state, &ctx.summaries()), state)
+    }
+
+    #[test]
+    fn parameters_are_not_listed_as_values() {
+        let (report, state) = report("
+            function sign(key: SharedRead) {
+              read key
+            }
+
+            function main {
+              region stack s
+              let k in s
+            }
+        ");
+        let names: Vec<_> = report.values.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, ["k"]);
+        assert!(!report.render(&state).contains("(key)"));
+    }
+
+    #[test]
+    fn json_report_lists_violations_with_typed_errors() {
+        let (report, _) = report("
+            function f {
+              region heap h
+              let v in h
+            }
+        ");
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+
+        assert_eq!(json["verdict"], "UNSAFE");
+        assert_eq!(json["destruction_ok"], false);
+        let violation = &json["violations"][0];
+        assert_eq!(violation["code"], "CH0043");
+        assert_eq!(violation["kind"], "Destruction");
+        assert_eq!(violation["span"]["line"], 2);
+        assert_eq!(violation["error"]["type"], "Leak");
+        assert_eq!(violation["error"]["value"], "v");
+        assert!(json.get("error").is_none());
+    }
+
+    #[test]
+    fn derived_values_carry_the_assumptions_of_their_sources() {
+        let (report, _) = report(r#"
+            function main {
+              region external device assume "the device initialized its buffer"
+              region stack s
+              lifetime l bound to main
+              let a in device
+              capability SharedRead a during l
+              let b from a in s
+              capability SharedRead b during l
+              let c from b in s
+              let d in s
+              release SharedRead a
+              return a to device
+            }
+        "#);
+        assert!(report.violations.is_empty());
+        let value = |name: &str| report.values.iter().find(|v| v.name == name).unwrap();
+
+        assert_eq!(value("c").origin, "UNSAFE");
+        assert_eq!(value("c").derived_from, ["b"]);
+        let chain = &value("c").assumptions;
+        assert_eq!(chain.len(), 1);
+        assert_eq!(chain[0].via, ["b", "a"]);
+        assert_eq!(value("a").assumptions[0].id, chain[0].id);
+        assert!(value("a").assumptions[0].via.is_empty());
+
+        assert_eq!(value("d").origin, "SAFE");
+        assert!(value("d").assumptions.is_empty());
+    }
+}
@@ -1,17 +1,21 @@
-use crate::graph::ScopeId;
-
-#[derive(Debug)]
-pub struct ScopeNode {
-    pub id: ScopeId,
-    pub parent: Option<ScopeId>,
-    pub kind: ScopeKind,
-    pub active: bool,
-}
-
-#[derive(Debug)]
-pub enum ScopeKind {
-    Module,
-    Function,
-    Block,
-    Unsafe,
-}
+use crate::graph::ScopeId;
+
+#[derive(Debug, Clone)]
+pub struct ScopeNode {
+    pub parent: Option<ScopeId>,
+    pub kind: ScopeKind,
+    /// Functions are named after the function and `block NAME` after its
+    /// name; other blocks are anonymous.
+    pub name: Option<String>,
+    pub active: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum ScopeKind {
+    Module,
+    Function,
+    Block,
+    Unsafe,
+    Spawn,
+    Declassify,
+}
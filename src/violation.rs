@@ -0,0 +1,42 @@
+use serde::Serialize;
+
+use crate::ast::Span;
+use crate::error::ChiruError;
+
+/// The safety property a violation breaks.
+///
+/// Each kind maps onto one line of the report summary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ViolationKind {
+    Ownership,
+    Lifetimes,
+    Capabilities,
+    Destruction,
+    Declarations,
+    Zeroization,
+    InformationFlow,
+}
+
+/// A recorded violation: the error plus the statement that caused it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Violation {
+    pub code: &'static str,
+    pub kind: ViolationKind,
+    pub message: String,
+    pub statement: String,
+    pub span: Span,
+    pub error: ChiruError,
+}
+
+impl Violation {
+    pub fn new(error: ChiruError, statement: String, span: Span) -> Self {
+        Violation {
+            code: error.code(),
+            kind: error.kind(),
+            message: error.to_string(),
+            statement,
+            span,
+            error,
+        }
+    }
+}